@@ -0,0 +1,336 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use apollo_compiler::collections::IndexSet;
+use apollo_federation::query_plan::query_planner::QueryPlannerConfig;
+
+use crate::AnalyzerError;
+use crate::EnumerationMode;
+use crate::OperationInfo;
+use crate::PreparedQuery;
+use crate::QueryPlanResult;
+use crate::Result;
+use crate::check_override_conditions;
+use crate::combinations::OverrideCombinations;
+use crate::combinations::combination_name;
+use crate::plan_with_conditions;
+use crate::prepare_planner;
+
+/// Accumulates the inputs and options of an `Analyzer` (see `Analyzer::builder`).
+/// - The supergraph schema and the query are required. The other options have defaults: the
+///   default planner configuration, the only operation of the query document, and all
+///   combinations of override conditions.
+#[derive(Default)]
+pub struct AnalyzerBuilder<'a> {
+    schema_str: Option<&'a str>,
+    query: Option<(&'a str, PathBuf)>,
+    config: QueryPlannerConfig,
+    operation_name: Option<String>,
+    mode: EnumerationMode,
+    cancelled: Option<Arc<AtomicBool>>,
+    plan_timeout: Option<Duration>,
+    max_combinations: Option<u64>,
+}
+
+impl<'a> AnalyzerBuilder<'a> {
+    /// Sets the supergraph schema document.
+    pub fn schema(mut self, schema_str: &'a str) -> Self {
+        self.schema_str = Some(schema_str);
+        self
+    }
+
+    /// Sets the query document, and its path (which labels the diagnostics of invalid queries).
+    pub fn query(mut self, query_str: &'a str, query_path: impl AsRef<Path>) -> Self {
+        self.query = Some((query_str, query_path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Sets the query planner configuration.
+    pub fn planner_config(mut self, config: QueryPlannerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Selects the operation to plan, if the query document defines several operations.
+    pub fn operation_name(mut self, operation_name: impl Into<String>) -> Self {
+        self.operation_name = Some(operation_name.into());
+        self
+    }
+
+    /// Sets which combinations of override conditions `Analyzer::build_all_plans` plans.
+    pub fn enumeration_mode(mut self, mode: EnumerationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Caps the number of combinations `Analyzer::build_all_plans` plans.
+    /// - If the enumeration mode yields more combinations, planning fails with
+    ///   `AnalyzerError::TooManyCombinations` before planning any of them.
+    pub fn max_combinations(mut self, max_combinations: u64) -> Self {
+        self.max_combinations = Some(max_combinations);
+        self
+    }
+
+    /// Limits the time spent planning each combination of override conditions.
+    /// - The query planner gives up on a combination once the time limit is reached, and the
+    ///   combination fails with `AnalyzerError::PlanningTimeout` (instead of running on).
+    pub fn plan_timeout(mut self, timeout: Duration) -> Self {
+        self.plan_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a flag cancelling `Analyzer::build_all_plans` once it's set (from another thread).
+    /// - The flag is checked before planning each combination. The combinations being planned are
+    ///   still completed, but their results are discarded: a cancelled run fails with
+    ///   `AnalyzerError::Cancelled`, without partial results.
+    pub fn cancellation_flag(mut self, cancelled: Arc<AtomicBool>) -> Self {
+        self.cancelled = Some(cancelled);
+        self
+    }
+
+    /// Builds the query planner for the supergraph schema and validates the query against its API
+    /// schema.
+    pub fn build(self) -> Result<Analyzer> {
+        let schema_str = self.schema_str.ok_or_else(|| {
+            AnalyzerError::InvalidInput("No supergraph schema was provided".into())
+        })?;
+        let (query_str, query_path) = self
+            .query
+            .ok_or_else(|| AnalyzerError::InvalidInput("No query was provided".into()))?;
+        let mut prepared = prepare_planner(
+            schema_str,
+            query_str,
+            query_path,
+            self.config,
+            self.operation_name.as_deref(),
+        )?;
+        prepared.plan_timeout = self.plan_timeout;
+        Ok(Analyzer {
+            prepared,
+            mode: self.mode,
+            cancelled: self.cancelled,
+            max_combinations: self.max_combinations,
+        })
+    }
+}
+
+/// A combination of override conditions that failed to plan (see `Analyzer::build_each_plan`)
+#[derive(Debug)]
+pub struct CombinationFailure {
+    /// The enabled override conditions of the combination
+    pub override_conditions: Vec<String>,
+    /// The name of the combination (see `combination_name`)
+    pub combination_name: String,
+    pub error: AnalyzerError,
+}
+
+/// Plans a query under combinations of override conditions.
+/// - The query planner is built once, and reused for every plan.
+pub struct Analyzer {
+    prepared: PreparedQuery,
+    mode: EnumerationMode,
+    cancelled: Option<Arc<AtomicBool>>,
+    max_combinations: Option<u64>,
+}
+
+impl Analyzer {
+    pub fn builder<'a>() -> AnalyzerBuilder<'a> {
+        AnalyzerBuilder::default()
+    }
+
+    /// Returns the override condition labels of the supergraph schema.
+    pub fn override_labels(&self) -> &IndexSet<Arc<str>> {
+        self.prepared.planner.override_condition_labels()
+    }
+
+    /// Lists the operations of the query document.
+    pub fn operations(&self) -> Vec<OperationInfo> {
+        self.prepared
+            .query_doc
+            .operations
+            .iter()
+            .map(|operation| OperationInfo {
+                name: operation.name.as_ref().map(|name| name.to_string()),
+                operation_type: operation.operation_type.to_string(),
+            })
+            .collect()
+    }
+
+    /// Enumerates combinations of override conditions (see `AnalyzerBuilder::enumeration_mode`)
+    /// and builds query plans for them.
+    /// - With the `parallel` feature, combinations are planned in parallel on rayon's thread pool.
+    ///   Otherwise (like in WASM builds), they are planned sequentially. Either way, the results
+    ///   are in enumeration order.
+    pub fn build_all_plans(&self) -> Result<Vec<QueryPlanResult>> {
+        self.build_all_plans_with_progress(None)
+    }
+
+    /// Same as `build_all_plans`, but calls `progress` with the numbers of completed and total
+    /// combinations after each combination is planned (successfully or not).
+    /// - With the `parallel` feature, `progress` is called from rayon's worker threads. Each call
+    ///   reports a distinct number of completed combinations, but concurrent calls may overtake
+    ///   each other.
+    pub fn build_all_plans_with_progress(
+        &self,
+        progress: Option<&(dyn Fn(usize, usize) + Sync)>,
+    ) -> Result<Vec<QueryPlanResult>> {
+        let override_combinations = self.override_combinations()?;
+        let total = override_combinations.len();
+        let completed = AtomicUsize::new(0);
+        let plan = |override_conditions: Vec<String>| {
+            if self.is_cancelled() {
+                return Err(AnalyzerError::Cancelled);
+            }
+            let result = plan_with_conditions(&self.prepared, override_conditions);
+            if let Some(progress) = progress {
+                progress(completed.fetch_add(1, Ordering::Relaxed) + 1, total);
+            }
+            result
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            override_combinations.into_par_iter().map(plan).collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            override_combinations.into_iter().map(plan).collect()
+        }
+    }
+
+    /// Same as `build_all_plans`, but a combination failing to plan doesn't fail the run: each
+    /// combination yields either its query plan result or its failure (in enumeration order).
+    /// - The run still fails if the combinations can't be enumerated (or there are too many of
+    ///   them), or if it's cancelled.
+    pub fn build_each_plan(
+        &self,
+    ) -> Result<Vec<std::result::Result<QueryPlanResult, CombinationFailure>>> {
+        let override_combinations = self.override_combinations()?;
+        let plan = |override_conditions: Vec<String>| {
+            if self.is_cancelled() {
+                return Err(AnalyzerError::Cancelled);
+            }
+            Ok(
+                plan_with_conditions(&self.prepared, override_conditions.clone()).map_err(
+                    |error| CombinationFailure {
+                        combination_name: combination_name(
+                            self.override_labels(),
+                            &override_conditions,
+                        ),
+                        override_conditions,
+                        error,
+                    },
+                ),
+            )
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            override_combinations.into_par_iter().map(plan).collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            override_combinations.into_iter().map(plan).collect()
+        }
+    }
+
+    /// Enumerates the combinations of override conditions to plan, checking their number against
+    /// `AnalyzerBuilder::max_combinations` first.
+    fn override_combinations(&self) -> Result<Vec<Vec<String>>> {
+        let override_labels = self.override_labels();
+        tracing::info!("Override condition labels: {override_labels:?}");
+
+        Ok(self.enumerate_combinations(self.mode)?.collect())
+    }
+
+    /// Returns the (lazy) enumeration of the combinations of `mode`, after checking their number
+    /// against `AnalyzerBuilder::max_combinations`.
+    fn enumerate_combinations(&self, mode: EnumerationMode) -> Result<OverrideCombinations> {
+        let override_labels = self.override_labels();
+        let count = mode.combination_count(override_labels.len());
+        if let Some(max) = self.max_combinations
+            && count > max as u128
+        {
+            return Err(AnalyzerError::TooManyCombinations { count, max });
+        }
+        OverrideCombinations::new(override_labels, mode)
+    }
+
+    /// Whether the cancellation flag is set (see `AnalyzerBuilder::cancellation_flag`).
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+            .as_ref()
+            .is_some_and(|cancelled| cancelled.load(Ordering::Relaxed))
+    }
+
+    /// Lazily plans the combinations of override conditions enumerated by `mode`, yielding one
+    /// result per combination (in enumeration order).
+    /// - Each combination is planned when its result is requested, so results can be processed and
+    ///   dropped one at a time.
+    /// - If the combinations can't be enumerated (e.g. there are too many labels, or more than
+    ///   `AnalyzerBuilder::max_combinations`), the only item is the error.
+    /// - Once the cancellation flag is set, the next item is `AnalyzerError::Cancelled`, and the
+    ///   iteration stops.
+    pub fn plan_combinations(
+        &self,
+        mode: EnumerationMode,
+    ) -> impl Iterator<Item = Result<QueryPlanResult>> + '_ {
+        let (combinations, error) = match self.enumerate_combinations(mode) {
+            Ok(combinations) => (Some(combinations), None),
+            Err(e) => (None, Some(Err(e))),
+        };
+        let mut cancelled = false;
+        error
+            .into_iter()
+            .chain(
+                combinations
+                    .into_iter()
+                    .flatten()
+                    .map_while(move |override_conditions| {
+                        if cancelled {
+                            return None;
+                        }
+                        if self.is_cancelled() {
+                            cancelled = true;
+                            return Some(Err(AnalyzerError::Cancelled));
+                        }
+                        tracing::info!("Override condition combination: {override_conditions:?}");
+                        Some(plan_with_conditions(&self.prepared, override_conditions))
+                    }),
+            )
+    }
+
+    /// Builds a query plan for the given state (enabled or not) of override condition labels.
+    /// - Only the labels whose state is `true` become active override conditions.
+    /// - Every label must be known to the supergraph schema (even the disabled ones), and must be
+    ///   listed at most once.
+    pub fn build_plan_with_states(
+        &self,
+        condition_states: &[(String, bool)],
+    ) -> Result<QueryPlanResult> {
+        let override_labels = self.override_labels();
+        tracing::info!("Override condition labels: {override_labels:?}");
+
+        let labels: Vec<String> = condition_states
+            .iter()
+            .map(|(label, _)| label.clone())
+            .collect();
+        check_override_conditions(override_labels, &labels)?;
+
+        let override_conditions = condition_states
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(label, _)| label.clone())
+            .collect();
+        plan_with_conditions(&self.prepared, override_conditions)
+    }
+}
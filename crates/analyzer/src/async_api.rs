@@ -0,0 +1,96 @@
+use std::path::PathBuf;
+
+use apollo_federation::internal_error;
+use apollo_federation::query_plan::query_planner::QueryPlannerConfig;
+
+use crate::AnalyzerError;
+use crate::EnumerationMode;
+use crate::OperationInfo;
+use crate::QueryPlanResult;
+use crate::Result;
+
+/// Runs the planning function on tokio's blocking thread pool.
+/// - Query planning is CPU-bound and synchronous, so it's kept off the async runtime's worker
+///   threads. The inputs are owned, since they are moved to the blocking thread.
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AnalyzerError::Planning(internal_error!("Query planning task failed: {e}")))?
+}
+
+/// Async version of `list_operations`.
+pub async fn list_operations_async(
+    schema_str: String,
+    query_str: String,
+    query_path: PathBuf,
+) -> Result<Vec<OperationInfo>> {
+    run_blocking(move || crate::list_operations(&schema_str, &query_str, query_path)).await
+}
+
+/// Async version of `build_all_plans_for_operation`.
+pub async fn build_all_plans_async(
+    schema_str: String,
+    query_str: String,
+    query_path: PathBuf,
+    config: QueryPlannerConfig,
+    operation_name: Option<String>,
+    mode: EnumerationMode,
+) -> Result<Vec<QueryPlanResult>> {
+    run_blocking(move || {
+        crate::build_all_plans_for_operation(
+            &schema_str,
+            &query_str,
+            query_path,
+            config,
+            operation_name.as_deref(),
+            mode,
+        )
+    })
+    .await
+}
+
+/// Async version of `build_one_plan`.
+pub async fn build_one_plan_async(
+    schema_str: String,
+    query_str: String,
+    query_path: PathBuf,
+    config: QueryPlannerConfig,
+    override_all: bool,
+    override_conditions: Option<Vec<String>>,
+) -> Result<QueryPlanResult> {
+    run_blocking(move || {
+        crate::build_one_plan(
+            &schema_str,
+            &query_str,
+            query_path,
+            config,
+            override_all,
+            override_conditions,
+        )
+    })
+    .await
+}
+
+/// Async version of `build_one_plan_with_states`.
+pub async fn build_one_plan_with_states_async(
+    schema_str: String,
+    query_str: String,
+    query_path: PathBuf,
+    config: QueryPlannerConfig,
+    operation_name: Option<String>,
+    condition_states: Vec<(String, bool)>,
+) -> Result<QueryPlanResult> {
+    run_blocking(move || {
+        crate::build_one_plan_with_states(
+            &schema_str,
+            &query_str,
+            query_path,
+            config,
+            operation_name.as_deref(),
+            &condition_states,
+        )
+    })
+    .await
+}
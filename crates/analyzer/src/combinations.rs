@@ -0,0 +1,278 @@
+use std::collections::BTreeSet;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use apollo_compiler::collections::IndexSet;
+use rand::Rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::AnalyzerError;
+use crate::Result;
+
+/// How the combinations of override conditions are enumerated.
+/// - In every mode, combinations are enumerated in the order described in `OverrideCombinations`.
+#[derive(Clone, Copy, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnumerationMode {
+    /// All 2^n combinations of n labels
+    #[default]
+    All,
+    /// No overrides, plus each label enabled alone (n+1 combinations)
+    OneHot,
+    /// No overrides, each label enabled alone, plus each pair of labels enabled together
+    /// (1 + n + n(n-1)/2 combinations)
+    Pairwise,
+    /// `count` distinct combinations picked at random (all combinations, if there are fewer)
+    /// - The same `seed` always picks the same combinations.
+    Sample { count: u64, seed: u64 },
+}
+
+impl EnumerationMode {
+    /// Returns the number of combinations of `label_count` labels enumerated in this mode, without
+    /// enumerating them (saturating at `u128::MAX`).
+    pub fn combination_count(&self, label_count: usize) -> u128 {
+        let n = label_count as u128;
+        let all = u32::try_from(label_count)
+            .ok()
+            .and_then(|label_count| 1u128.checked_shl(label_count))
+            .unwrap_or(u128::MAX);
+        match self {
+            EnumerationMode::All => all,
+            EnumerationMode::OneHot => n + 1,
+            EnumerationMode::Pairwise => 1 + n + n * n.saturating_sub(1) / 2,
+            EnumerationMode::Sample { count, .. } => all.min(*count as u128),
+        }
+    }
+}
+
+/// An iterator over combinations of override conditions.
+/// - Each combination is represented as a bitmask over the labels, where bit `i` is set if the
+///   `i`-th label is enabled. Labels are ordered as in `override_condition_labels()`.
+/// - Combinations are yielded in ascending bitmask order. For example, the labels `[a, b, c]` are
+///   enumerated as `[]`, `[a]`, `[b]`, `[a, b]`, `[c]`, `[a, c]`, `[b, c]`, `[a, b, c]`.
+/// - The labels of each combination are listed in label order.
+/// - Each combination can be planned with `Analyzer::build_plan_with_states`, for custom planning
+///   loops.
+pub struct OverrideCombinations {
+    labels: Vec<String>,
+    masks: Masks,
+}
+
+enum Masks {
+    Range(RangeInclusive<u64>),
+    List(std::vec::IntoIter<u64>),
+}
+
+impl OverrideCombinations {
+    /// Enumerates the combinations of the labels (like `Analyzer::override_labels`) in the mode.
+    /// - Fails if there are more than 64 labels, which don't fit in a bitmask.
+    pub fn new(labels: &IndexSet<Arc<str>>, mode: EnumerationMode) -> Result<Self> {
+        let label_count = labels.len();
+        if label_count > u64::BITS as usize {
+            return Err(AnalyzerError::InvalidInput(format!(
+                "Too many override condition labels to enumerate: {label_count} (at most {} are supported)",
+                u64::BITS
+            )));
+        }
+        Ok(match mode {
+            EnumerationMode::All => Self::all(labels),
+            EnumerationMode::OneHot => Self::one_hot(labels),
+            EnumerationMode::Pairwise => Self::pairwise(labels),
+            EnumerationMode::Sample { count, seed } => Self::sample(labels, count, seed),
+        })
+    }
+
+    /// All possible combinations.
+    fn all(labels: &IndexSet<Arc<str>>) -> Self {
+        Self::with_masks(labels, Masks::Range(0..=max_mask(labels.len())))
+    }
+
+    /// The empty combination and single-label combinations.
+    fn one_hot(labels: &IndexSet<Arc<str>>) -> Self {
+        let masks = std::iter::once(0).chain(single_masks(labels.len()));
+        Self::with_masks(labels, Masks::List(masks.collect::<Vec<_>>().into_iter()))
+    }
+
+    /// The empty combination, single-label combinations and label pairs.
+    fn pairwise(labels: &IndexSet<Arc<str>>) -> Self {
+        let label_count = labels.len();
+        let pair_masks =
+            (0..label_count).flat_map(|i| ((i + 1)..label_count).map(move |j| (1 << i) | (1 << j)));
+        let mut masks: Vec<u64> = std::iter::once(0)
+            .chain(single_masks(label_count))
+            .chain(pair_masks)
+            .collect();
+        masks.sort_unstable();
+        Self::with_masks(labels, Masks::List(masks.into_iter()))
+    }
+
+    /// Distinct combinations picked using a seeded random number generator, in increasing bitmask
+    /// order.
+    fn sample(labels: &IndexSet<Arc<str>>, count: u64, seed: u64) -> Self {
+        let max_mask = max_mask(labels.len());
+        if count > max_mask {
+            // The sample covers all combinations.
+            return Self::all(labels);
+        }
+        if count == 0 {
+            return Self::with_masks(labels, Masks::List(Vec::new().into_iter()));
+        }
+
+        // Floyd's algorithm: picks `count` distinct values from `0..=max_mask` in `count` steps.
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut picked = BTreeSet::new();
+        for j in (max_mask - count + 1)..=max_mask {
+            let candidate = rng.random_range(0..=j);
+            if !picked.insert(candidate) {
+                picked.insert(j);
+            }
+        }
+        Self::with_masks(labels, Masks::List(Vec::from_iter(picked).into_iter()))
+    }
+
+    fn with_masks(labels: &IndexSet<Arc<str>>, masks: Masks) -> Self {
+        OverrideCombinations {
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+            masks,
+        }
+    }
+
+    fn labels_of(&self, mask: u64) -> Vec<String> {
+        self.labels
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| mask & (1 << i) != 0)
+            .map(|(_, label)| label.clone())
+            .collect()
+    }
+}
+
+impl Iterator for OverrideCombinations {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mask = match &mut self.masks {
+            Masks::Range(range) => range.next(),
+            Masks::List(list) => list.next(),
+        }?;
+        Some(self.labels_of(mask))
+    }
+}
+
+/// Names the combination by the state of every label, like `{labelA=on, labelB=off}`.
+pub fn combination_name(
+    override_labels: &IndexSet<Arc<str>>,
+    override_conditions: &[String],
+) -> String {
+    let states: Vec<_> = override_labels
+        .iter()
+        .map(|label| {
+            let enabled = override_conditions
+                .iter()
+                .any(|cond| cond == label.as_ref());
+            format!("{label}={}", if enabled { "on" } else { "off" })
+        })
+        .collect();
+    format!("{{{}}}", states.join(", "))
+}
+
+/// Computes the bitmask of the enabled labels (the inverse of the enumeration's bitmask mapping).
+/// - Labels beyond the 64th do not fit in the bitmask and are ignored.
+pub(crate) fn combination_mask(
+    override_labels: &IndexSet<Arc<str>>,
+    override_conditions: &[String],
+) -> u64 {
+    override_conditions
+        .iter()
+        .filter_map(|cond| override_labels.get_index_of(cond.as_str()))
+        .filter_map(|i| 1u64.checked_shl(i as u32))
+        .fold(0, |mask, bit| mask | bit)
+}
+
+/// The bitmasks with exactly one of the `label_count` lowest bits set, in increasing order.
+fn single_masks(label_count: usize) -> impl Iterator<Item = u64> {
+    (0..label_count).map(|i| 1 << i)
+}
+
+/// The bitmask with all of the `label_count` lowest bits set.
+fn max_mask(label_count: usize) -> u64 {
+    match label_count {
+        0 => 0,
+        _ => u64::MAX >> (u64::BITS as usize - label_count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enumerate(labels: &[&str], mode: EnumerationMode) -> Vec<Vec<String>> {
+        let labels: IndexSet<Arc<str>> = labels.iter().map(|label| Arc::from(*label)).collect();
+        OverrideCombinations::new(&labels, mode).unwrap().collect()
+    }
+
+    #[test]
+    fn all_combinations_are_in_ascending_bitmask_order() {
+        assert_eq!(
+            enumerate(&["a", "b", "c"], EnumerationMode::All),
+            vec![
+                vec![],
+                vec!["a"],
+                vec!["b"],
+                vec!["a", "b"],
+                vec!["c"],
+                vec!["a", "c"],
+                vec!["b", "c"],
+                vec!["a", "b", "c"],
+            ],
+        );
+    }
+
+    #[test]
+    fn no_labels_yield_the_empty_combination() {
+        assert_eq!(
+            enumerate(&[], EnumerationMode::All),
+            vec![Vec::<String>::new()]
+        );
+    }
+
+    #[test]
+    fn one_hot_and_pairwise_combinations_are_in_ascending_bitmask_order() {
+        assert_eq!(
+            enumerate(&["a", "b", "c"], EnumerationMode::OneHot),
+            vec![vec![], vec!["a"], vec!["b"], vec!["c"]],
+        );
+        assert_eq!(
+            enumerate(&["a", "b", "c"], EnumerationMode::Pairwise),
+            vec![
+                vec![],
+                vec!["a"],
+                vec!["b"],
+                vec!["a", "b"],
+                vec!["c"],
+                vec!["a", "c"],
+                vec!["b", "c"],
+            ],
+        );
+    }
+
+    #[test]
+    fn samples_are_deterministic_and_ordered() {
+        let labels = ["a", "b", "c", "d", "e"];
+        let sample = enumerate(&labels, EnumerationMode::Sample { count: 6, seed: 7 });
+        assert_eq!(sample.len(), 6);
+        assert_eq!(
+            sample,
+            enumerate(&labels, EnumerationMode::Sample { count: 6, seed: 7 })
+        );
+
+        let all = enumerate(&labels, EnumerationMode::All);
+        let positions: Vec<_> = sample
+            .iter()
+            .map(|combination| all.iter().position(|c| c == combination).unwrap())
+            .collect();
+        assert!(positions.is_sorted());
+        assert!(positions.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+}
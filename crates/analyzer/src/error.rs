@@ -0,0 +1,110 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use apollo_compiler::validation::DiagnosticList;
+use apollo_federation::error::FederationError;
+
+/// An error of the analyzer, by failure mode
+/// - Errors of the query planner are kept as the source of the variant they're classified as, and
+///   the diagnostics of invalid queries are kept as they are (to be rendered with the source).
+#[derive(Debug)]
+pub enum AnalyzerError {
+    /// The supergraph schema is invalid, or no query planner can be built for it
+    SchemaComposition(FederationError),
+    /// The query document failed to parse, or to validate against the supergraph's API schema
+    QueryValidation {
+        /// The path labeling the query document in the diagnostics
+        path: PathBuf,
+        /// The diagnostics of the query document, which point at the offending source text
+        diagnostics: DiagnosticList,
+    },
+    /// The query document doesn't define the selected operation
+    UnknownOperation { name: String },
+    /// An override condition label isn't defined by the supergraph schema
+    UnknownOverrideLabel {
+        label: String,
+        /// The defined label closest to the unknown one, if any is close enough
+        suggestion: Option<String>,
+        /// The labels defined by the supergraph schema
+        available: Vec<String>,
+    },
+    /// An override condition label is listed more than once
+    DuplicateOverrideLabel { label: String },
+    /// The supergraph schema doesn't compose a subgraph of that name
+    UnknownSubgraph {
+        name: String,
+        /// The subgraphs composed into the supergraph schema
+        available: Vec<String>,
+    },
+    /// The query planner failed to plan the query (or panicked)
+    Planning(FederationError),
+    /// The query planner didn't plan a combination within the time limit (see
+    /// `AnalyzerBuilder::plan_timeout`)
+    PlanningTimeout {
+        /// The enabled override conditions of the combination
+        override_conditions: Vec<String>,
+        timeout: Duration,
+    },
+    /// There are more combinations of override conditions to plan than allowed (see
+    /// `AnalyzerBuilder::max_combinations`)
+    TooManyCombinations { count: u128, max: u64 },
+    /// The inputs are incomplete or inconsistent (like options that can't be used together)
+    InvalidInput(String),
+    /// The planning was cancelled (see `AnalyzerBuilder::cancellation_flag`)
+    Cancelled,
+}
+
+impl fmt::Display for AnalyzerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalyzerError::SchemaComposition(e) | AnalyzerError::Planning(e) => write!(f, "{e}"),
+            AnalyzerError::QueryValidation { diagnostics, .. } => write!(f, "{diagnostics}"),
+            AnalyzerError::UnknownOperation { name } => write!(f, "Unknown operation: {name}"),
+            AnalyzerError::UnknownOverrideLabel {
+                label,
+                suggestion,
+                available,
+            } => {
+                write!(f, "Unknown override condition label: {label}.")?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, " Did you mean `{suggestion}`?")?;
+                }
+                write!(f, " Available labels: {available:?}")
+            }
+            AnalyzerError::DuplicateOverrideLabel { label } => {
+                write!(f, "Duplicate override condition label: {label}")
+            }
+            AnalyzerError::UnknownSubgraph { name, available } => write!(
+                f,
+                "Unknown subgraph `{name}` (available subgraphs: {})",
+                available.join(", ")
+            ),
+            AnalyzerError::PlanningTimeout {
+                override_conditions,
+                timeout,
+            } => write!(
+                f,
+                "Query planning timed out after {timeout:?} with override conditions {override_conditions:?}"
+            ),
+            AnalyzerError::TooManyCombinations { count, max } => write!(
+                f,
+                "Too many combinations of override conditions to plan: {count} (at most {max} are allowed)"
+            ),
+            AnalyzerError::InvalidInput(message) => write!(f, "{message}"),
+            AnalyzerError::Cancelled => write!(f, "The query planning was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for AnalyzerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AnalyzerError::SchemaComposition(e) | AnalyzerError::Planning(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The result of the analyzer's functions
+pub type Result<T> = std::result::Result<T, AnalyzerError>;
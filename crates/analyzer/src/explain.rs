@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::fmt;
+
+use apollo_compiler::ast;
+use apollo_federation::query_plan::QueryPlan;
+
+use crate::node::Node;
+use crate::render::display_path;
+use crate::stats::plan_stats;
+use crate::subgraphs::plan_subgraphs;
+
+/// A structured explanation of the changes between two query plans (see `explain_plan_changes`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct PlanChanges {
+    /// The subgraphs fetched from by the second plan only
+    pub subgraphs_added: BTreeSet<String>,
+    /// The subgraphs fetched from by the first plan only
+    pub subgraphs_removed: BTreeSet<String>,
+    /// The number of fetches of the second plan, minus the one of the first plan
+    pub fetch_count_delta: isize,
+    /// The nesting depth of the second plan, minus the one of the first plan
+    pub depth_delta: isize,
+    /// The number of fetches on the critical path of the second plan, minus the one of the
+    /// first plan
+    pub critical_path_delta: isize,
+    /// The fields fetched by both plans, but not from the same subgraphs (in path order)
+    pub moved_fields: Vec<MovedField>,
+}
+
+/// A field whose value is fetched from different subgraphs by two query plans
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct MovedField {
+    /// The response path of the field (e.g. `user.email`, or `users.@.email` for list items)
+    pub path: String,
+    /// The subgraphs fetching the field in the first plan
+    pub from: BTreeSet<String>,
+    /// The subgraphs fetching the field in the second plan
+    pub to: BTreeSet<String>,
+}
+
+impl PlanChanges {
+    /// Whether the two plans are structurally the same, as far as this explanation goes.
+    pub fn is_empty(&self) -> bool {
+        *self == PlanChanges::default()
+    }
+}
+
+/// Explains the changes between two query plans of the same operation.
+/// - Fields are correlated by their response path: the path of the enclosing `Flatten` node,
+///   followed by the path of the selection in the fetch's subgraph operation. Entity fetches
+///   contribute the fields selected on the `_entities` representations.
+/// - Fields can be fetched from several subgraphs by the same plan (like entity keys), so they
+///   move when the set of subgraphs changes.
+pub fn explain_plan_changes(from: &QueryPlan, to: &QueryPlan) -> PlanChanges {
+    let subgraphs_from = plan_subgraphs(from);
+    let subgraphs_to = plan_subgraphs(to);
+    let stats_from = plan_stats(from);
+    let stats_to = plan_stats(to);
+    let fields_from = field_subgraphs(from);
+    let fields_to = field_subgraphs(to);
+    let moved_fields = fields_from
+        .into_iter()
+        .filter_map(|(path, from)| {
+            let to = fields_to.get(&path)?;
+            (from != *to).then(|| MovedField {
+                path,
+                from,
+                to: to.clone(),
+            })
+        })
+        .collect();
+    PlanChanges {
+        subgraphs_added: subgraphs_to.difference(&subgraphs_from).cloned().collect(),
+        subgraphs_removed: subgraphs_from.difference(&subgraphs_to).cloned().collect(),
+        fetch_count_delta: delta(stats_from.fetch_count, stats_to.fetch_count),
+        depth_delta: delta(stats_from.max_depth, stats_to.max_depth),
+        critical_path_delta: delta(
+            stats_from.critical_path_length,
+            stats_to.critical_path_length,
+        ),
+        moved_fields,
+    }
+}
+
+fn delta(from: usize, to: usize) -> isize {
+    to as isize - from as isize
+}
+
+/// Maps the response path of each field fetched by the plan to the subgraphs fetching it.
+fn field_subgraphs(plan: &QueryPlan) -> BTreeMap<String, BTreeSet<String>> {
+    let mut fields = BTreeMap::new();
+    let mut stack: Vec<_> = Node::root(plan)
+        .into_iter()
+        .map(|node| (node, String::new()))
+        .collect();
+    while let Some((node, prefix)) = stack.pop() {
+        match node {
+            Node::Fetch(fetch) => {
+                // Note: Operations that fail to parse are skipped (they are generated by the
+                //       query planner, so they shouldn't).
+                let Ok(document) =
+                    ast::Document::parse(fetch.operation_document.as_serialized(), "fetch.graphql")
+                else {
+                    continue;
+                };
+                let fragments: HashMap<_, _> = document
+                    .definitions
+                    .iter()
+                    .filter_map(|definition| match definition {
+                        ast::Definition::FragmentDefinition(fragment) => {
+                            Some((fragment.name.as_str(), &fragment.selection_set))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                for definition in &document.definitions {
+                    if let ast::Definition::OperationDefinition(operation) = definition {
+                        collect_fields(
+                            &operation.selection_set,
+                            &prefix,
+                            &fragments,
+                            fetch.subgraph_name.as_ref(),
+                            &mut fields,
+                        );
+                    }
+                }
+            }
+            Node::Flatten(flatten) => {
+                let path = display_path(&flatten.path);
+                stack.push((Node::from(&*flatten.node), join_path(&prefix, &path)));
+            }
+            _ => stack.extend(
+                node.children()
+                    .into_iter()
+                    .map(|child| (child, prefix.clone())),
+            ),
+        }
+    }
+    fields
+}
+
+fn collect_fields(
+    selections: &[ast::Selection],
+    prefix: &str,
+    fragments: &HashMap<&str, &Vec<ast::Selection>>,
+    subgraph: &str,
+    fields: &mut BTreeMap<String, BTreeSet<String>>,
+) {
+    for selection in selections {
+        match selection {
+            ast::Selection::Field(field) => {
+                let name = field.response_key();
+                if name == "__typename" {
+                    continue;
+                }
+                // The entity representations resolve to the fields of the flattened path.
+                if field.name == "_entities" {
+                    collect_fields(&field.selection_set, prefix, fragments, subgraph, fields);
+                    continue;
+                }
+                let path = join_path(prefix, name);
+                collect_fields(&field.selection_set, &path, fragments, subgraph, fields);
+                fields.entry(path).or_default().insert(subgraph.to_string());
+            }
+            ast::Selection::InlineFragment(fragment) => {
+                collect_fields(&fragment.selection_set, prefix, fragments, subgraph, fields);
+            }
+            ast::Selection::FragmentSpread(spread) => {
+                if let Some(selection_set) = fragments.get(spread.fragment_name.as_str()) {
+                    collect_fields(selection_set, prefix, fragments, subgraph, fields);
+                }
+            }
+        }
+    }
+}
+
+fn join_path(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{prefix}.{name}")
+    }
+}
+
+impl fmt::Display for PlanChanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Subgraphs added: {:?}, removed: {:?}",
+            self.subgraphs_added, self.subgraphs_removed
+        )?;
+        writeln!(f, "Fetch count delta: {:+}", self.fetch_count_delta)?;
+        writeln!(f, "Depth delta: {:+}", self.depth_delta)?;
+        write!(f, "Critical path delta: {:+}", self.critical_path_delta)?;
+        for field in &self.moved_fields {
+            write!(
+                f,
+                "\nField `{}` now resolves from {:?} instead of {:?}",
+                field.path, field.to, field.from
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use std::fmt::Write;
+
+use apollo_compiler::Schema;
+use apollo_compiler::ast;
+use apollo_federation::query_plan::QueryPlan;
+use sha2::Digest;
+use sha2::Sha256;
+
+use crate::QueryPlanResult;
+use crate::node::Node;
+use crate::render::display_path;
+
+/// Returns a short, stable fingerprint of the query plan.
+/// - Two plans have the same fingerprint if and only if their displays are identical.
+/// - The fingerprint is stable across runs, so it can be recorded and compared later.
+pub fn plan_fingerprint(plan: &QueryPlan) -> String {
+    short_digest(&plan.to_string())
+}
+
+impl QueryPlanResult {
+    /// Returns the fingerprint of the query plan (see `plan_fingerprint`).
+    pub fn fingerprint(&self) -> String {
+        short_digest(&self.query_plan_display)
+    }
+}
+
+/// A hashable key identifying a query plan, to group equivalent plans with standard collections
+/// (like `HashMap<PlanKey, Vec<QueryPlanResult>>`).
+/// - Equality is structural, not textual: two keys are equal if their plans have the same tree of
+///   nodes, with fetches of the same (normalized) operations from the same subgraphs, under the
+///   same flatten paths and conditions. The text of the plan display, the formatting of the
+///   subgraph operations, and the combination or run the plan came from, don't matter.
+/// - The key wraps a digest of the plan structure, which is stable across runs, but isn't the
+///   same as the plan's fingerprint (see `plan_fingerprint`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PlanKey(String);
+
+impl PlanKey {
+    /// Returns the digest of the plan structure.
+    pub fn fingerprint(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&QueryPlan> for PlanKey {
+    fn from(plan: &QueryPlan) -> Self {
+        let mut structure = String::new();
+        if let Some(root) = Node::root(plan) {
+            write_structure(root, &mut structure);
+        }
+        PlanKey(short_digest(&structure))
+    }
+}
+
+impl From<&QueryPlanResult> for PlanKey {
+    fn from(result: &QueryPlanResult) -> Self {
+        PlanKey::from(&result.experimental_query_plan_serialized)
+    }
+}
+
+/// Writes a canonical description of the node and its descendants (see `PlanKey`).
+fn write_structure(node: Node<'_>, out: &mut String) {
+    // Note: Writing to a `String` doesn't fail.
+    match node {
+        Node::Fetch(fetch) => {
+            let operation = fetch.operation_document.as_serialized();
+            // Subgraph operations are normalized by parsing and printing them.
+            let operation = match ast::Document::parse(operation, "fetch.graphql") {
+                Ok(document) => document.to_string(),
+                Err(_) => operation.to_string(),
+            };
+            let _ = write!(
+                out,
+                "Fetch({:?},{:?},{operation:?})",
+                &*fetch.subgraph_name, fetch.requires
+            );
+            return;
+        }
+        Node::Condition(condition) => {
+            // The branches are labeled, since either one may be absent.
+            let _ = write!(out, "Condition({}", condition.condition_variable);
+            for (label, branch) in [
+                ("if", &condition.if_clause),
+                ("else", &condition.else_clause),
+            ] {
+                if let Some(branch) = branch.as_deref() {
+                    let _ = write!(out, ",{label}:");
+                    write_structure(Node::from(branch), out);
+                }
+            }
+            out.push(')');
+            return;
+        }
+        Node::Flatten(flatten) => {
+            let _ = write!(out, "Flatten({:?},", display_path(&flatten.path));
+        }
+        Node::Sequence(_) => out.push_str("Sequence("),
+        Node::Parallel(_) => out.push_str("Parallel("),
+        Node::Defer(_) => out.push_str("Defer("),
+        Node::Subscription(_) => out.push_str("Subscription("),
+    }
+    for (i, child) in node.children().into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_structure(child, out);
+    }
+    out.push(')');
+}
+
+impl std::fmt::Display for PlanKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Returns a short digest of the normalized supergraph schema.
+/// - The schema is normalized by parsing and printing it, so that formatting and comments don't
+///   affect the digest. If it fails to parse, the digest of the text as-is is returned.
+pub(crate) fn schema_hash(schema_str: &str) -> String {
+    match Schema::parse(schema_str, "supergraph.graphql") {
+        Ok(schema) => short_digest(&schema.to_string()),
+        Err(_) => short_digest(schema_str),
+    }
+}
+
+/// The first 8 bytes of the SHA-256 digest of the text, in hexadecimal.
+pub(crate) fn short_digest(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .take(8)
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
@@ -1,16 +1,90 @@
+use std::collections::HashMap;
+use std::ops::ControlFlow;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use apollo_compiler::ExecutableDocument;
+use apollo_compiler::Name;
+use apollo_compiler::ast;
 use apollo_compiler::collections::IndexSet;
-use apollo_federation::error::FederationError;
+use apollo_compiler::validation::Valid;
 use apollo_federation::internal_error;
 use apollo_federation::query_plan::QueryPlan;
 use apollo_federation::query_plan::query_planner::QueryPlanOptions;
 use apollo_federation::query_plan::query_planner::QueryPlanner;
 use apollo_federation::query_plan::query_planner::QueryPlannerConfig;
+use web_time::Instant;
 
-#[derive(serde::Serialize, serde::Deserialize)]
+mod analyzer;
+#[cfg(feature = "tokio")]
+mod async_api;
+mod combinations;
+mod error;
+mod explain;
+mod fingerprint;
+mod node;
+mod node_ids;
+mod overrides;
+mod panics;
+mod render;
+mod router_json;
+mod stats;
+mod subgraphs;
+mod warnings;
+
+pub use analyzer::Analyzer;
+pub use analyzer::AnalyzerBuilder;
+pub use analyzer::CombinationFailure;
+#[cfg(feature = "tokio")]
+pub use async_api::build_all_plans_async;
+#[cfg(feature = "tokio")]
+pub use async_api::build_one_plan_async;
+#[cfg(feature = "tokio")]
+pub use async_api::build_one_plan_with_states_async;
+#[cfg(feature = "tokio")]
+pub use async_api::list_operations_async;
+pub use combinations::EnumerationMode;
+pub use combinations::OverrideCombinations;
+use combinations::combination_mask;
+pub use combinations::combination_name;
+pub use error::AnalyzerError;
+pub use error::Result;
+pub use explain::MovedField;
+pub use explain::PlanChanges;
+pub use explain::explain_plan_changes;
+pub use fingerprint::PlanKey;
+pub use fingerprint::plan_fingerprint;
+use fingerprint::schema_hash;
+use fingerprint::short_digest;
+pub use node_ids::IdentifiedNode;
+pub use node_ids::plan_to_identified_nodes;
+pub use overrides::OverrideTarget;
+pub use overrides::describe_override_labels;
+pub use overrides::override_label_percentage;
+pub use render::DEFAULT_TREE_INDENT;
+pub use render::TreeOptions;
+pub use render::annotate_display_subgraphs;
+pub use render::plan_to_compact;
+pub use render::plan_to_dot;
+pub use render::plan_to_mermaid;
+pub use render::plan_to_tree;
+pub use render::plan_to_tree_with_options;
+pub use router_json::plan_to_router_json;
+pub use stats::CostWeights;
+pub use stats::QueryPlanStats;
+pub use stats::plan_cost;
+pub use stats::plan_stats;
+pub use subgraphs::SubgraphInfo;
+pub use subgraphs::list_subgraphs;
+pub use subgraphs::plan_subgraphs;
+pub use subgraphs::subgraph_sdl;
+use warnings::query_warnings;
+
+/// The result of planning a query under a combination of override conditions.
+/// - Maps in the result must be ordered (`IndexMap` or `BTreeMap`, not `HashMap`), so that the
+///   serialized result is identical across runs and platforms.
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 pub struct QueryPlanResult {
     /// The configuration affecting the generation of this query plan
     pub query_plan_config: QueryPlanConfig,
@@ -19,70 +93,232 @@ pub struct QueryPlanResult {
     pub query_plan_display: String,
 
     /// (experimental) Apollo's internal representation of the generated query plan
+    /// - Its shape is not stable and is not described by the output schema.
+    #[schemars(with = "serde_json::Value")]
     pub experimental_query_plan_serialized: QueryPlan,
+
+    /// The wall-clock time spent building this query plan, in milliseconds
+    #[serde(default)]
+    pub planning_duration_ms: f64,
+
+    /// A short digest of the normalized supergraph schema this query plan was built for
+    /// - Formatting and comments of the schema document don't affect the digest.
+    #[serde(default)]
+    pub schema_hash: String,
+
+    /// A short digest of the normalized query document this query plan was built for
+    /// - Formatting and comments of the query document don't affect the digest.
+    #[serde(default)]
+    pub query_hash: String,
+
+    /// The name of the planned operation, if it was selected by name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation_name: Option<String>,
+
+    /// Non-fatal issues of the query document, which didn't prevent planning it (like the
+    /// selection of deprecated fields)
+    /// - Always serialized, as an empty array if there are none.
+    #[serde(default)]
+    pub warnings: Vec<String>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 pub struct QueryPlanConfig {
+    /// The override condition labels that were enabled for this query plan
     pub override_conditions: Vec<String>,
+
+    /// The state of every override condition label (e.g. `{labelA=on, labelB=off}`)
+    #[serde(default)]
+    pub combination_name: String,
+
+    /// The bitmask of the enabled labels, where bit `i` stands for the `i`-th label of the
+    /// supergraph's override condition labels
+    /// - Labels beyond the 64th are not represented.
+    #[serde(default)]
+    pub combination_mask: u64,
+
+    /// The query planner options this query plan was built with
+    #[serde(default)]
+    pub planner_config: PlannerConfigSnapshot,
+}
+
+/// A snapshot of the query planner options that affect the generated query plans
+#[derive(
+    Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub struct PlannerConfigSnapshot {
+    /// Whether subgraph fetch queries are optimized using fragments
+    pub generate_query_fragments: bool,
+    /// Whether `@defer` is supported
+    pub enable_defer: bool,
+    /// Whether type conditioned fetching is enabled
+    pub type_conditioned_fetching: bool,
+    /// Whether subgraph fetch queries are validated against their subgraph schemas
+    pub subgraph_graphql_validation: bool,
+    /// The limit to the number of evaluated query plans
+    pub max_evaluated_plans: u32,
+    /// The per-path limit to the number of options considered (None if unlimited)
+    pub paths_limit: Option<u32>,
+}
+
+impl From<&QueryPlannerConfig> for PlannerConfigSnapshot {
+    fn from(config: &QueryPlannerConfig) -> Self {
+        PlannerConfigSnapshot {
+            generate_query_fragments: config.generate_query_fragments,
+            enable_defer: config.incremental_delivery.enable_defer,
+            type_conditioned_fetching: config.type_conditioned_fetching,
+            subgraph_graphql_validation: config.subgraph_graphql_validation,
+            max_evaluated_plans: config.debug.max_evaluated_plans.get(),
+            paths_limit: config.debug.paths_limit,
+        }
+    }
+}
+
+/// Returns the JSON Schema describing a serialized `QueryPlanResult`.
+pub fn query_plan_result_schema() -> schemars::Schema {
+    schemars::schema_for!(QueryPlanResult)
 }
 
-pub fn get_override_labels(schema_str: &str) -> Result<IndexSet<Arc<str>>, FederationError> {
-    let supergraph = apollo_federation::Supergraph::new_with_router_specs(schema_str)?;
-    let planner = QueryPlanner::new(&supergraph, QueryPlannerConfig::default())?;
+/// Checks that the supergraph schema is valid, without parsing any query.
+/// - Lets callers fail fast on a bad schema, before planning anything.
+pub fn validate_schema(schema_str: &str) -> Result<()> {
+    load_supergraph(schema_str)?;
+    Ok(())
+}
+
+/// Loads the supergraph schema (its errors are `SchemaComposition` errors).
+pub(crate) fn load_supergraph(schema_str: &str) -> Result<apollo_federation::Supergraph> {
+    apollo_federation::Supergraph::new_with_router_specs(schema_str)
+        .map_err(AnalyzerError::SchemaComposition)
+}
+
+/// Builds the query planner for the supergraph schema (its errors are `SchemaComposition` errors).
+fn new_planner(schema_str: &str, config: QueryPlannerConfig) -> Result<QueryPlanner> {
+    let supergraph = load_supergraph(schema_str)?;
+    QueryPlanner::new(&supergraph, config).map_err(AnalyzerError::SchemaComposition)
+}
+
+pub fn get_override_labels(schema_str: &str) -> Result<IndexSet<Arc<str>>> {
+    let planner = new_planner(schema_str, QueryPlannerConfig::default())?;
     let override_labels = planner.override_condition_labels();
     Ok(override_labels.clone())
 }
 
-/// Enumerate all possible combinations of override conditions and build query plans for them.
-pub fn build_all_plans(
+/// Returns the number of combinations of override conditions (2^n for n labels), without
+/// enumerating them.
+/// - Fails if the count doesn't fit in a `u128` (128 labels or more).
+pub fn combination_count(schema_str: &str) -> Result<u128> {
+    let label_count = get_override_labels(schema_str)?.len();
+    u32::try_from(label_count)
+        .ok()
+        .and_then(|label_count| 1u128.checked_shl(label_count))
+        .ok_or_else(|| {
+            AnalyzerError::InvalidInput(format!(
+                "Too many override condition labels to count: {label_count}"
+            ))
+        })
+}
+
+/// Returns the API schema of the supergraph (the schema exposed to clients) as SDL.
+/// - The API schema depends on the planner configuration (for example, whether `@defer` is
+///   supported).
+pub fn api_schema_sdl(schema_str: &str, config: QueryPlannerConfig) -> Result<String> {
+    let planner = new_planner(schema_str, config)?;
+    Ok(planner.api_schema().schema().to_string())
+}
+
+/// Returns the names of the operations defined in the query document, in document order.
+/// - The anonymous operation, if any, is listed as None.
+pub fn operation_names(
+    query_str: &str,
+    query_path: impl AsRef<Path>,
+) -> Result<Vec<Option<String>>> {
+    // Syntax errors are reported with their location in the query document.
+    let query_path = query_path.as_ref();
+    let document = ast::Document::parse(query_str, query_path).map_err(|e| {
+        AnalyzerError::QueryValidation {
+            path: query_path.to_path_buf(),
+            diagnostics: e.errors,
+        }
+    })?;
+    Ok(document
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            ast::Definition::OperationDefinition(operation) => {
+                Some(operation.name.as_ref().map(|name| name.to_string()))
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// Parses the query document, and validates it against the supergraph's API schema (with the
+/// default planner configuration).
+/// - Invalid queries fail with diagnostics pointing at the offending source text.
+pub fn parse_query(
     schema_str: &str,
     query_str: &str,
     query_path: impl AsRef<Path>,
-    config: QueryPlannerConfig,
-    verbose: bool,
-) -> Result<Vec<QueryPlanResult>, FederationError> {
-    let supergraph = apollo_federation::Supergraph::new_with_router_specs(schema_str)?;
-    let planner = QueryPlanner::new(&supergraph, config)?;
-
-    let query_doc = ExecutableDocument::parse_and_validate(
-        planner.api_schema().schema(),
-        query_str,
-        query_path,
-    )
-    .map_err(FederationError::from)?;
+) -> Result<Valid<ExecutableDocument>> {
+    let planner = new_planner(schema_str, QueryPlannerConfig::default())?;
+    parse_query_with(&planner, query_str, query_path)
+}
 
-    let override_labels = planner.override_condition_labels();
-    tracing::info!("Override condition labels: {override_labels:?}");
+/// An operation defined by a query document
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OperationInfo {
+    /// The operation name (None for the anonymous operation)
+    pub name: Option<String>,
+    /// The operation type (`query`, `mutation` or `subscription`)
+    pub operation_type: String,
+}
 
-    // enumerate all combinations of override labels.
-    let override_combinations = generate_all_possible_override_conditions(override_labels);
-    tracing::info!("Override condition combinations: {override_combinations:#?}");
+/// Lists the operations of the query document, after validating it against the supergraph's API
+/// schema.
+pub fn list_operations(
+    schema_str: &str,
+    query_str: &str,
+    query_path: impl AsRef<Path>,
+) -> Result<Vec<OperationInfo>> {
+    let analyzer = Analyzer::builder()
+        .schema(schema_str)
+        .query(query_str, query_path)
+        .build()?;
+    Ok(analyzer.operations())
+}
 
-    let mut results = Vec::new();
-    for (i, override_conditions) in override_combinations.into_iter().enumerate() {
-        if verbose {
-            println!("-----------------------------------------------------------------------");
-            println!("Override Combination #{i}: {override_conditions:?}");
-            println!("-----------------------------------------------------------------------");
-        }
-        let qp_opts = QueryPlanOptions {
-            override_conditions: override_conditions.clone(),
-            ..Default::default()
-        };
-        let query_plan = planner.build_query_plan(&query_doc, None, qp_opts)?;
-        if verbose {
-            println!("{query_plan}\n");
-        }
-        results.push(QueryPlanResult {
-            query_plan_config: QueryPlanConfig {
-                override_conditions,
-            },
-            query_plan_display: format!("{query_plan}"),
-            experimental_query_plan_serialized: query_plan,
-        });
+/// Enumerate combinations of override conditions and build query plans for them.
+/// - `mode` selects which combinations are enumerated (all possible combinations by default).
+pub fn build_all_plans(
+    schema_str: &str,
+    query_str: &str,
+    query_path: impl AsRef<Path>,
+    config: QueryPlannerConfig,
+    mode: EnumerationMode,
+) -> Result<Vec<QueryPlanResult>> {
+    build_all_plans_for_operation(schema_str, query_str, query_path, config, None, mode)
+}
+
+/// Same as `build_all_plans`, but plans the named operation of a multi-operation document.
+/// - With no operation name, the document must define exactly one operation.
+pub fn build_all_plans_for_operation(
+    schema_str: &str,
+    query_str: &str,
+    query_path: impl AsRef<Path>,
+    config: QueryPlannerConfig,
+    operation_name: Option<&str>,
+    mode: EnumerationMode,
+) -> Result<Vec<QueryPlanResult>> {
+    let mut builder = Analyzer::builder()
+        .schema(schema_str)
+        .query(query_str, query_path)
+        .planner_config(config)
+        .enumeration_mode(mode);
+    if let Some(operation_name) = operation_name {
+        builder = builder.operation_name(operation_name);
     }
-    Ok(results)
+    builder.build()?.build_all_plans()
 }
 
 pub fn build_one_plan(
@@ -92,86 +328,278 @@ pub fn build_one_plan(
     config: QueryPlannerConfig,
     override_all: bool,
     override_conditions: Option<Vec<String>>,
-) -> Result<QueryPlanResult, FederationError> {
-    let supergraph = apollo_federation::Supergraph::new_with_router_specs(schema_str)?;
-    let planner = QueryPlanner::new(&supergraph, config)?;
-
-    let query_doc = ExecutableDocument::parse_and_validate(
-        planner.api_schema().schema(),
-        query_str,
-        query_path,
-    )
-    .map_err(FederationError::from)?;
+) -> Result<QueryPlanResult> {
+    let prepared = prepare_planner(schema_str, query_str, query_path, config, None)?;
 
-    let override_labels = planner.override_condition_labels();
+    let override_labels = prepared.planner.override_condition_labels();
     tracing::info!("Override condition labels: {override_labels:?}");
 
     let override_conditions = if override_all {
         if override_conditions.is_some() {
-            return Err(internal_error!(
-                "`override_all` cannot be used with specific override conditions",
+            return Err(AnalyzerError::InvalidInput(
+                "`override_all` cannot be used with specific override conditions".into(),
             ));
         }
         override_labels.iter().map(|s| s.to_string()).collect()
     } else {
-        let override_conditions = override_conditions
-            .ok_or_else(|| internal_error!("No override conditions were provided"))?;
+        let override_conditions = override_conditions.ok_or_else(|| {
+            AnalyzerError::InvalidInput("No override conditions were provided".into())
+        })?;
         check_override_conditions(override_labels, &override_conditions)?;
         override_conditions
     };
 
+    plan_with_conditions(&prepared, override_conditions)
+}
+
+/// Builds a query plan for the given state (enabled or not) of override condition labels.
+/// - Only the labels whose state is `true` become active override conditions.
+/// - Every label must be known to the supergraph schema (even the disabled ones), and must be
+///   listed at most once.
+/// - `operation_name` selects the operation of a multi-operation document.
+pub fn build_one_plan_with_states(
+    schema_str: &str,
+    query_str: &str,
+    query_path: impl AsRef<Path>,
+    config: QueryPlannerConfig,
+    operation_name: Option<&str>,
+    condition_states: &[(String, bool)],
+) -> Result<QueryPlanResult> {
+    let mut builder = Analyzer::builder()
+        .schema(schema_str)
+        .query(query_str, query_path)
+        .planner_config(config);
+    if let Some(operation_name) = operation_name {
+        builder = builder.operation_name(operation_name);
+    }
+    builder.build()?.build_plan_with_states(condition_states)
+}
+
+/// Finds the smallest set of override conditions whose query plan has the target fingerprint.
+/// - Combinations are searched in increasing size order (and in enumeration order within the
+///   same size).
+/// - Returns None, if no combination yields the target query plan.
+pub fn minimal_override_set_for(
+    schema_str: &str,
+    query_str: &str,
+    query_path: impl AsRef<Path>,
+    config: QueryPlannerConfig,
+    target_fingerprint: &str,
+) -> Result<Option<Vec<String>>> {
+    let prepared = prepare_planner(schema_str, query_str, query_path, config, None)?;
+
+    let mut override_combinations: Vec<_> = OverrideCombinations::new(
+        prepared.planner.override_condition_labels(),
+        EnumerationMode::All,
+    )?
+    .collect();
+    // Note: The sort is stable, which preserves the enumeration order within the same size.
+    override_combinations.sort_by_key(|override_conditions| override_conditions.len());
+
+    for override_conditions in override_combinations {
+        let result = plan_with_conditions(&prepared, override_conditions)?;
+        if result.fingerprint() == target_fingerprint {
+            return Ok(Some(result.query_plan_config.override_conditions));
+        }
+    }
+    Ok(None)
+}
+
+/// Finds the override condition labels that the query is insensitive to.
+/// - A label is insensitive, if toggling it never changes the query plan, regardless of the
+///   other labels' states.
+/// - All combinations are planned, so this is only practical for a moderate number of labels.
+/// - `operation_name` selects the operation of a multi-operation document.
+pub fn insensitive_override_labels(
+    schema_str: &str,
+    query_str: &str,
+    query_path: impl AsRef<Path>,
+    config: QueryPlannerConfig,
+    operation_name: Option<&str>,
+) -> Result<Vec<String>> {
+    let prepared = prepare_planner(schema_str, query_str, query_path, config, operation_name)?;
+    let override_labels = prepared.planner.override_condition_labels();
+
+    let mut fingerprints = HashMap::new();
+    for override_conditions in OverrideCombinations::new(override_labels, EnumerationMode::All)? {
+        let result = plan_with_conditions(&prepared, override_conditions)?;
+        fingerprints.insert(
+            result.query_plan_config.combination_mask,
+            result.fingerprint(),
+        );
+    }
+
+    let insensitive = override_labels
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| {
+            let bit = 1u64 << i;
+            fingerprints
+                .iter()
+                .filter(|(mask, _)| *mask & bit == 0)
+                .all(|(mask, fingerprint)| fingerprints.get(&(mask | bit)) == Some(fingerprint))
+        })
+        .map(|(_, label)| label.to_string())
+        .collect();
+    Ok(insensitive)
+}
+
+/// Builds the query planner for the supergraph schema and parses the query against its API schema.
+/// - If an operation name is given, the operation must be defined by the query document.
+fn prepare_planner(
+    schema_str: &str,
+    query_str: &str,
+    query_path: impl AsRef<Path>,
+    config: QueryPlannerConfig,
+    operation_name: Option<&str>,
+) -> Result<PreparedQuery> {
+    let planner_config = PlannerConfigSnapshot::from(&config);
+    let planner = new_planner(schema_str, config)?;
+
+    let query_doc = parse_query_with(&planner, query_str, query_path)?;
+    let operation_name = match operation_name {
+        Some(name) => {
+            let unknown = || AnalyzerError::UnknownOperation {
+                name: name.to_string(),
+            };
+            query_doc
+                .operations
+                .get(Some(name))
+                .map_err(|_| unknown())?;
+            Some(Name::new(name).map_err(|_| unknown())?)
+        }
+        None => None,
+    };
+    Ok(PreparedQuery {
+        warnings: query_warnings(&query_doc),
+        schema_hash: schema_hash(schema_str),
+        // The printed document is normalized (without the original formatting and comments).
+        query_hash: short_digest(&query_doc.to_string()),
+        planner_config,
+        planner,
+        query_doc,
+        operation_name,
+        plan_timeout: None,
+    })
+}
+
+/// Parses the query, and validates it against the planner's API schema.
+fn parse_query_with(
+    planner: &QueryPlanner,
+    query_str: &str,
+    query_path: impl AsRef<Path>,
+) -> Result<Valid<ExecutableDocument>> {
+    // The diagnostics of invalid queries point at the offending source text (labeled with the
+    // query path, with line and column numbers and a snippet of the source).
+    let query_path = query_path.as_ref();
+    ExecutableDocument::parse_and_validate(planner.api_schema().schema(), query_str, query_path)
+        .map_err(|e| AnalyzerError::QueryValidation {
+            path: query_path.to_path_buf(),
+            diagnostics: e.errors,
+        })
+}
+
+/// The query planner and the query to plan, prepared once per run.
+struct PreparedQuery {
+    planner: QueryPlanner,
+    query_doc: Valid<ExecutableDocument>,
+    /// The operation to plan (None, if the document defines a single operation)
+    operation_name: Option<Name>,
+    /// Stamped on every result (see `QueryPlanResult::schema_hash`)
+    schema_hash: String,
+    /// Stamped on every result (see `QueryPlanResult::query_hash`)
+    query_hash: String,
+    /// Stamped on every result (see `QueryPlanConfig::planner_config`)
+    planner_config: PlannerConfigSnapshot,
+    /// Stamped on every result (see `QueryPlanResult::warnings`)
+    warnings: Vec<String>,
+    /// The time limit of planning each combination (see `AnalyzerBuilder::plan_timeout`)
+    plan_timeout: Option<Duration>,
+}
+
+fn plan_with_conditions(
+    prepared: &PreparedQuery,
+    override_conditions: Vec<String>,
+) -> Result<QueryPlanResult> {
+    let PreparedQuery {
+        planner,
+        query_doc,
+        operation_name,
+        schema_hash,
+        query_hash,
+        planner_config,
+        warnings,
+        plan_timeout,
+    } = prepared;
+    let start = Instant::now();
+    // The planner checks the deadline cooperatively, and gives up once it's passed.
+    let deadline = plan_timeout.map(|timeout| start + timeout);
+    let timed_out = || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+    let check_deadline = || {
+        if timed_out() {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    };
     let qp_opts = QueryPlanOptions {
         override_conditions: override_conditions.clone(),
+        check_for_cooperative_cancellation: deadline
+            .map(|_| &check_deadline as &dyn Fn() -> ControlFlow<()>),
         ..Default::default()
     };
-    let query_plan = planner.build_query_plan(&query_doc, None, qp_opts)?;
+    // Some inputs make the planner panic. Report them as errors of this combination.
+    let planned = panics::catch_panic(|| {
+        planner.build_query_plan(query_doc, operation_name.clone(), qp_opts)
+    });
+    let query_plan = match planned {
+        Ok(query_plan) => query_plan.map_err(|e| match plan_timeout {
+            Some(timeout) if timed_out() => AnalyzerError::PlanningTimeout {
+                override_conditions: override_conditions.clone(),
+                timeout: *timeout,
+            },
+            _ => AnalyzerError::Planning(e),
+        })?,
+        Err(message) => {
+            return Err(AnalyzerError::Planning(internal_error!(
+                "Query planner panicked with override conditions {override_conditions:?}: {message}"
+            )));
+        }
+    };
+    let planning_duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let override_labels = planner.override_condition_labels();
     Ok(QueryPlanResult {
         query_plan_config: QueryPlanConfig {
+            combination_name: combination_name(override_labels, &override_conditions),
+            combination_mask: combination_mask(override_labels, &override_conditions),
             override_conditions,
+            planner_config: planner_config.clone(),
         },
         query_plan_display: format!("{query_plan}"),
         experimental_query_plan_serialized: query_plan,
+        planning_duration_ms,
+        schema_hash: schema_hash.clone(),
+        query_hash: query_hash.clone(),
+        operation_name: operation_name.as_ref().map(|name| name.to_string()),
+        warnings: warnings.clone(),
     })
 }
 
-fn generate_all_possible_override_conditions(labels: &IndexSet<Arc<str>>) -> Vec<Vec<String>> {
-    let mut result = Vec::new(); // all collected combinations
-    let mut state = Vec::new(); // current (partial) combination
-    fn inner_generate<'a>(
-        result: &mut Vec<Vec<String>>,
-        state: &mut Vec<Arc<str>>,
-        mut remaining: impl Iterator<Item = &'a Arc<str>> + Clone,
-    ) {
-        match remaining.next() {
-            None => {
-                // No more labels left, add the current state to the result
-                result.push(state.iter().rev().map(|s| s.to_string()).collect());
-            }
-            Some(label) => {
-                // Exclude the current label from the state
-                inner_generate(result, state, remaining.clone());
-
-                // Include the current label in the state
-                state.push(label.clone());
-                inner_generate(result, state, remaining);
-                state.pop(); // backtrack to remove the last added label
-            }
-        }
-    }
-    inner_generate(&mut result, &mut state, labels.iter().rev());
-    result
-}
-
 fn check_override_conditions(
     override_labels: &IndexSet<Arc<str>>,
     override_conditions: &[String],
-) -> Result<(), FederationError> {
+) -> Result<()> {
     // Check invalid labels
     for cond in override_conditions {
         if !override_labels.contains(cond.as_str()) {
-            return Err(internal_error!(
-                "Unknown override condition label: {cond}. Available labels: {override_labels:?}"
-            ));
+            return Err(AnalyzerError::UnknownOverrideLabel {
+                label: cond.clone(),
+                suggestion: closest_label(override_labels, cond).map(|label| label.to_string()),
+                available: override_labels
+                    .iter()
+                    .map(|label| label.to_string())
+                    .collect(),
+            });
         }
     }
 
@@ -179,15 +607,26 @@ fn check_override_conditions(
     let mut seen = IndexSet::default();
     for cond in override_conditions {
         if !seen.insert(cond) {
-            return Err(internal_error!(
-                "Duplicate override condition label: {cond}"
-            ));
+            return Err(AnalyzerError::DuplicateOverrideLabel {
+                label: cond.clone(),
+            });
         }
     }
 
     Ok(())
 }
 
+/// Finds the known label closest to the unknown one by edit distance.
+/// - Labels that differ in more than half of their characters are not considered close.
+fn closest_label<'a>(override_labels: &'a IndexSet<Arc<str>>, unknown: &str) -> Option<&'a str> {
+    override_labels
+        .iter()
+        .map(|label| (strsim::levenshtein(label, unknown), label.as_ref()))
+        .filter(|(distance, label)| *distance <= label.len().max(unknown.len()) / 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, label)| label)
+}
+
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct QueryPlanDifference {
     pub full_diff: String,
@@ -221,3 +660,57 @@ pub fn compare_query_plans(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    const SUPERGRAPH: &str = include_str!("../../../example/supergraph.graphql");
+    const QUERY: &str = include_str!("../../../example/op.graphql");
+
+    fn serialize_plans() -> String {
+        let mut results = build_all_plans(
+            SUPERGRAPH,
+            QUERY,
+            "op.graphql",
+            QueryPlannerConfig::default(),
+            EnumerationMode::All,
+        )
+        .unwrap();
+        // The planning time is the only field expected to vary between runs.
+        for result in &mut results {
+            result.planning_duration_ms = 0.0;
+        }
+        serde_json::to_string_pretty(&results).unwrap()
+    }
+
+    #[test]
+    fn serialized_results_are_identical_across_runs() {
+        assert_eq!(serialize_plans(), serialize_plans());
+    }
+
+    #[test]
+    fn subgraphs_match_the_plan_display() {
+        let results = build_all_plans(
+            SUPERGRAPH,
+            QUERY,
+            "op.graphql",
+            QueryPlannerConfig::default(),
+            EnumerationMode::All,
+        )
+        .unwrap();
+        for result in &results {
+            let displayed: BTreeSet<_> = result
+                .query_plan_display
+                .split("Fetch(service: \"")
+                .skip(1)
+                .filter_map(|rest| rest.split_once('"'))
+                .map(|(subgraph, _)| subgraph.to_string())
+                .collect();
+            assert!(!displayed.is_empty());
+            assert_eq!(result.subgraphs(), displayed);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use apollo_federation::query_plan::ConditionNode;
+use apollo_federation::query_plan::DeferNode;
+use apollo_federation::query_plan::FetchNode;
+use apollo_federation::query_plan::FlattenNode;
+use apollo_federation::query_plan::PlanNode;
+use apollo_federation::query_plan::QueryPlan;
+use apollo_federation::query_plan::SubscriptionNode;
+use apollo_federation::query_plan::TopLevelPlanNode;
+
+/// A borrowed query plan node.
+/// - Unifies `TopLevelPlanNode` and `PlanNode`, so that plan traversals are written only once.
+#[derive(Clone, Copy)]
+pub(crate) enum Node<'a> {
+    Subscription(&'a SubscriptionNode),
+    Fetch(&'a FetchNode),
+    Sequence(&'a [PlanNode]),
+    Parallel(&'a [PlanNode]),
+    Flatten(&'a FlattenNode),
+    Defer(&'a DeferNode),
+    Condition(&'a ConditionNode),
+}
+
+impl<'a> From<&'a TopLevelPlanNode> for Node<'a> {
+    fn from(node: &'a TopLevelPlanNode) -> Self {
+        match node {
+            TopLevelPlanNode::Subscription(node) => Node::Subscription(node),
+            TopLevelPlanNode::Fetch(node) => Node::Fetch(node),
+            TopLevelPlanNode::Sequence(node) => Node::Sequence(&node.nodes),
+            TopLevelPlanNode::Parallel(node) => Node::Parallel(&node.nodes),
+            TopLevelPlanNode::Flatten(node) => Node::Flatten(node),
+            TopLevelPlanNode::Defer(node) => Node::Defer(node),
+            TopLevelPlanNode::Condition(node) => Node::Condition(node),
+        }
+    }
+}
+
+impl<'a> From<&'a PlanNode> for Node<'a> {
+    fn from(node: &'a PlanNode) -> Self {
+        match node {
+            PlanNode::Fetch(node) => Node::Fetch(node),
+            PlanNode::Sequence(node) => Node::Sequence(&node.nodes),
+            PlanNode::Parallel(node) => Node::Parallel(&node.nodes),
+            PlanNode::Flatten(node) => Node::Flatten(node),
+            PlanNode::Defer(node) => Node::Defer(node),
+            PlanNode::Condition(node) => Node::Condition(node),
+        }
+    }
+}
+
+impl<'a> Node<'a> {
+    /// Returns the root node of the query plan (`None` if the plan is empty).
+    pub(crate) fn root(plan: &'a QueryPlan) -> Option<Self> {
+        plan.node.as_ref().map(Node::from)
+    }
+
+    /// Returns the direct children of this node in plan order.
+    pub(crate) fn children(self) -> Vec<Node<'a>> {
+        match self {
+            Node::Subscription(node) => std::iter::once(Node::Fetch(&node.primary))
+                .chain(node.rest.as_deref().map(Node::from))
+                .collect(),
+            Node::Fetch(_) => Vec::new(),
+            Node::Sequence(nodes) | Node::Parallel(nodes) => nodes.iter().map(Node::from).collect(),
+            Node::Flatten(node) => vec![Node::from(&*node.node)],
+            Node::Defer(node) => node
+                .primary
+                .node
+                .as_deref()
+                .into_iter()
+                .chain(
+                    node.deferred
+                        .iter()
+                        .filter_map(|block| block.node.as_deref()),
+                )
+                .map(Node::from)
+                .collect(),
+            Node::Condition(node) => node
+                .if_clause
+                .as_deref()
+                .into_iter()
+                .chain(node.else_clause.as_deref())
+                .map(Node::from)
+                .collect(),
+        }
+    }
+}
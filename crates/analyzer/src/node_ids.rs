@@ -0,0 +1,131 @@
+use apollo_federation::query_plan::QueryPlan;
+
+use crate::node::Node;
+use crate::render::display_path;
+
+/// A query plan node with a stable ID, in a JSON-friendly shape (see `plan_to_identified_nodes`).
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct IdentifiedNode {
+    /// The index of the node in the pre-order traversal of the plan (the root node is 0)
+    pub id: usize,
+    /// The node kind (`Fetch`, `Sequence`, `Parallel`, `Flatten`, `Defer`, `Condition` or
+    /// `Subscription`)
+    pub kind: &'static str,
+    /// The role of the node in its parent, if the parent distinguishes its children
+    /// - `primary` and `deferred` in a `Defer`, `primary` and `rest` in a `Subscription`, and `if`
+    ///   and `else` in a `Condition`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<&'static str>,
+    /// The label of the deferred block, for `deferred` nodes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// The subgraph fetched from, for `Fetch` nodes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subgraph: Option<String>,
+    /// The response path, for `Flatten` nodes (like `a.@.b`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// The condition variable, for `Condition` nodes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<IdentifiedNode>,
+}
+
+/// Returns the plan nodes as a tree of nodes with stable IDs (`None` if the plan is empty).
+/// - IDs are assigned in pre-order: a node gets its ID before its children, which are visited in
+///   plan order (the order of the query plan display). So, the same plan always gets the same IDs.
+/// - Children are visited like `Node::children`: the primary fetch before the rest of a
+///   subscription, the primary block before the deferred blocks of a defer, and the `if` branch
+///   before the `else` branch of a condition. Deferred blocks without a node are left out.
+pub fn plan_to_identified_nodes(plan: &QueryPlan) -> Option<IdentifiedNode> {
+    let mut next_id = 0;
+    Node::root(plan).map(|root| identify(root, None, &mut next_id))
+}
+
+fn identify(node: Node<'_>, branch: Option<&'static str>, next_id: &mut usize) -> IdentifiedNode {
+    let mut identified = IdentifiedNode {
+        id: *next_id,
+        kind: node_kind(node),
+        branch,
+        label: None,
+        subgraph: None,
+        path: None,
+        condition: None,
+        children: Vec::new(),
+    };
+    *next_id += 1;
+    // The children with their roles (and labels of deferred blocks), in plan order
+    let children: Vec<(Node<'_>, Option<&'static str>, Option<String>)> = match node {
+        Node::Fetch(fetch) => {
+            identified.subgraph = Some(fetch.subgraph_name.to_string());
+            Vec::new()
+        }
+        Node::Sequence(_) | Node::Parallel(_) => node
+            .children()
+            .into_iter()
+            .map(|child| (child, None, None))
+            .collect(),
+        Node::Flatten(flatten) => {
+            identified.path = Some(display_path(&flatten.path));
+            vec![(Node::from(&*flatten.node), None, None)]
+        }
+        Node::Subscription(subscription) => {
+            std::iter::once((Node::Fetch(&subscription.primary), Some("primary"), None))
+                .chain(
+                    subscription
+                        .rest
+                        .as_deref()
+                        .map(|rest| (Node::from(rest), Some("rest"), None)),
+                )
+                .collect()
+        }
+        Node::Defer(defer) => defer
+            .primary
+            .node
+            .as_deref()
+            .map(|primary| (Node::from(primary), Some("primary"), None))
+            .into_iter()
+            .chain(defer.deferred.iter().filter_map(|block| {
+                let node = block.node.as_deref()?;
+                Some((Node::from(node), Some("deferred"), block.label.clone()))
+            }))
+            .collect(),
+        Node::Condition(condition) => {
+            identified.condition = Some(condition.condition_variable.to_string());
+            condition
+                .if_clause
+                .as_deref()
+                .map(|if_clause| (Node::from(if_clause), Some("if"), None))
+                .into_iter()
+                .chain(
+                    condition
+                        .else_clause
+                        .as_deref()
+                        .map(|else_clause| (Node::from(else_clause), Some("else"), None)),
+                )
+                .collect()
+        }
+    };
+    // Children are identified in order, so that their IDs follow their parent's in pre-order.
+    identified.children = children
+        .into_iter()
+        .map(|(child, branch, label)| IdentifiedNode {
+            label,
+            ..identify(child, branch, next_id)
+        })
+        .collect();
+    identified
+}
+
+fn node_kind(node: Node<'_>) -> &'static str {
+    match node {
+        Node::Subscription(_) => "Subscription",
+        Node::Fetch(_) => "Fetch",
+        Node::Sequence(_) => "Sequence",
+        Node::Parallel(_) => "Parallel",
+        Node::Flatten(_) => "Flatten",
+        Node::Defer(_) => "Defer",
+        Node::Condition(_) => "Condition",
+    }
+}
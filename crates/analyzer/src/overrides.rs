@@ -0,0 +1,80 @@
+use apollo_compiler::Schema;
+use apollo_compiler::collections::IndexMap;
+use apollo_compiler::schema::ExtendedType;
+use apollo_federation::internal_error;
+
+use crate::AnalyzerError;
+use crate::Result;
+use crate::load_supergraph;
+
+/// A field that an override condition label applies to.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OverrideTarget {
+    /// The name of the type that defines the field
+    #[serde(rename = "type")]
+    pub type_name: String,
+    /// The name of the overridden field
+    #[serde(rename = "field")]
+    pub field_name: String,
+    /// The name of the subgraph the field is overridden from
+    pub from: String,
+}
+
+/// Collects the fields each override condition label applies to.
+/// - Walks the `@override(from:, label:)` directives, as encoded in the supergraph schema by
+///   `@join__field(override:, overrideLabel:)`.
+/// - Labels are listed in the order they first appear in the schema.
+pub fn describe_override_labels(schema_str: &str) -> Result<IndexMap<String, Vec<OverrideTarget>>> {
+    // Validate the supergraph the same way as the other entry points.
+    load_supergraph(schema_str)?;
+    let schema = Schema::parse(schema_str, "supergraph.graphql").map_err(|e| {
+        AnalyzerError::SchemaComposition(internal_error!(
+            "Failed to parse the supergraph schema: {}",
+            e.errors
+        ))
+    })?;
+
+    let mut targets: IndexMap<String, Vec<OverrideTarget>> = IndexMap::default();
+    for (type_name, ty) in &schema.types {
+        let fields = match ty {
+            ExtendedType::Object(ty) => &ty.fields,
+            ExtendedType::Interface(ty) => &ty.fields,
+            _ => continue,
+        };
+        for (field_name, field) in fields {
+            for directive in field.directives.get_all("join__field") {
+                let argument = |name: &str| {
+                    directive
+                        .specified_argument_by_name(name)
+                        .and_then(|value| value.as_str())
+                };
+                // Note: The field in the overridden subgraph also has the label, but no `override`.
+                let (Some(label), Some(from)) = (argument("overrideLabel"), argument("override"))
+                else {
+                    continue;
+                };
+                targets
+                    .entry(label.to_string())
+                    .or_default()
+                    .push(OverrideTarget {
+                        type_name: type_name.to_string(),
+                        field_name: field_name.to_string(),
+                        from: from.to_string(),
+                    });
+            }
+        }
+    }
+    Ok(targets)
+}
+
+/// Returns the rollout percentage of a progressive override label (e.g. `50` for `percent(50)`).
+/// - Returns None for other labels, which are feature flags resolved outside of the router's
+///   progressive override (e.g. by coprocessors or plugins).
+pub fn override_label_percentage(label: &str) -> Option<f64> {
+    label
+        .strip_prefix("percent(")?
+        .strip_suffix(')')?
+        .trim()
+        .parse()
+        .ok()
+}
@@ -0,0 +1,41 @@
+use std::any::Any;
+use std::cell::Cell;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::Once;
+
+thread_local! {
+    /// Whether the panic hook should stay silent on this thread
+    static SUPPRESS_PANIC_OUTPUT: Cell<bool> = const { Cell::new(false) };
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+/// Runs `f`, converting a panic into an error carrying the panic message.
+/// - The default panic output (message and backtrace) is suppressed while `f` runs, since the
+///   panic is reported as an error instead. Panics on other threads are reported as usual.
+pub(crate) fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    INSTALL_HOOK.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !SUPPRESS_PANIC_OUTPUT.get() {
+                default_hook(info);
+            }
+        }));
+    });
+
+    let suppressed = SUPPRESS_PANIC_OUTPUT.replace(true);
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    SUPPRESS_PANIC_OUTPUT.set(suppressed);
+    result.map_err(|payload| panic_message(payload.as_ref()))
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "(unknown panic payload)".to_string()
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::BTreeSet;
+use std::fmt::Display;
+
+use apollo_federation::query_plan::QueryPlan;
+
+use crate::fingerprint::short_digest;
+use crate::node::Node;
+
+/// The default indentation width of each tree level (see `TreeOptions::indent`)
+pub const DEFAULT_TREE_INDENT: usize = 3;
+
+/// Options of the tree rendering (see `plan_to_tree_with_options`).
+#[derive(Debug, Clone)]
+pub struct TreeOptions {
+    /// The number of columns per tree level (at least 2, for the connector and a space)
+    pub indent: usize,
+    /// Whether to prefix fetch lines with the subgraph they are sent to (like `[accounts]`)
+    pub annotate_subgraphs: bool,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        TreeOptions {
+            indent: DEFAULT_TREE_INDENT,
+            annotate_subgraphs: false,
+        }
+    }
+}
+
+/// Renders the query plan as an indented tree using Unicode box-drawing connectors.
+/// - Each line shows the node kind and, for fetches, the subgraph it is sent to.
+pub fn plan_to_tree(plan: &QueryPlan) -> String {
+    plan_to_tree_with_options(plan, &TreeOptions::default())
+}
+
+/// Same as `plan_to_tree`, with the given rendering options.
+pub fn plan_to_tree_with_options(plan: &QueryPlan, options: &TreeOptions) -> String {
+    let indent = TreeIndent::new(options.indent);
+    let mut lines = vec!["QueryPlan".to_string()];
+    if let Some(root) = Node::root(plan) {
+        let mut root = TreeItem::from_node(root);
+        if options.annotate_subgraphs {
+            root.annotate_subgraphs();
+        }
+        write_tree_items(&mut lines, &[root], "", &indent);
+    }
+    lines.join("\n")
+}
+
+/// Prefixes the fetch lines of the query plan display with the subgraph they are sent to.
+/// - For example, `Fetch(service: "accounts") {` becomes `[accounts] Fetch(service: "accounts") {`.
+/// - The query plan display is Apollo's rendering, so fetch lines are recognized by their text.
+pub fn annotate_display_subgraphs(display: &str) -> String {
+    display
+        .lines()
+        .map(|line| {
+            let content = line.trim_start();
+            let subgraph = content
+                .strip_prefix("Fetch(service: \"")
+                .and_then(|rest| rest.split_once('"'))
+                .map(|(subgraph, _)| subgraph);
+            match subgraph {
+                Some(subgraph) => {
+                    let indentation = &line[..line.len() - content.len()];
+                    format!("{indentation}[{subgraph}] {content}")
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The connectors of a tree level, padded to the indentation width
+struct TreeIndent {
+    connector: String,
+    last_connector: String,
+    continuation: String,
+    last_continuation: String,
+}
+
+impl TreeIndent {
+    fn new(width: usize) -> Self {
+        let width = width.max(2);
+        let line = "─".repeat(width - 2);
+        TreeIndent {
+            connector: format!("├{line} "),
+            last_connector: format!("└{line} "),
+            continuation: format!("│{}", " ".repeat(width - 1)),
+            last_continuation: " ".repeat(width),
+        }
+    }
+}
+
+/// A labelled tree entry.
+/// - Besides plan nodes, this includes synthetic entries like the branches of a condition.
+struct TreeItem {
+    label: String,
+    /// The subgraph fetched from, for fetch nodes
+    subgraph: Option<String>,
+    children: Vec<TreeItem>,
+}
+
+impl TreeItem {
+    /// Prefixes the labels of fetch entries with their subgraph (recursively).
+    fn annotate_subgraphs(&mut self) {
+        if let Some(subgraph) = &self.subgraph {
+            self.label = format!("[{subgraph}] {}", self.label);
+        }
+        for child in &mut self.children {
+            child.annotate_subgraphs();
+        }
+    }
+
+    fn new(label: impl Into<String>, children: Vec<Node<'_>>) -> Self {
+        TreeItem {
+            label: label.into(),
+            subgraph: None,
+            children: children.into_iter().map(TreeItem::from_node).collect(),
+        }
+    }
+
+    fn from_node(node: Node<'_>) -> Self {
+        match node {
+            Node::Fetch(fetch) => TreeItem {
+                subgraph: Some(fetch.subgraph_name.to_string()),
+                ..TreeItem::new(
+                    format!("Fetch(service: \"{}\")", fetch.subgraph_name),
+                    vec![],
+                )
+            },
+            Node::Sequence(_) => TreeItem::new("Sequence", node.children()),
+            Node::Parallel(_) => TreeItem::new("Parallel", node.children()),
+            Node::Flatten(flatten) => TreeItem::new(
+                format!("Flatten(path: \"{}\")", display_path(&flatten.path)),
+                node.children(),
+            ),
+            Node::Subscription(subscription) => {
+                let mut children = vec![TreeItem::new(
+                    "Primary",
+                    vec![Node::Fetch(&subscription.primary)],
+                )];
+                if let Some(rest) = subscription.rest.as_deref() {
+                    children.push(TreeItem::new("Rest", vec![Node::from(rest)]));
+                }
+                TreeItem {
+                    label: "Subscription".to_string(),
+                    subgraph: None,
+                    children,
+                }
+            }
+            Node::Defer(defer) => {
+                let mut children = vec![TreeItem::new(
+                    "Primary",
+                    defer
+                        .primary
+                        .node
+                        .as_deref()
+                        .map(Node::from)
+                        .into_iter()
+                        .collect(),
+                )];
+                for block in &defer.deferred {
+                    let label = match &block.label {
+                        Some(label) => format!("Deferred(label: \"{label}\")"),
+                        None => "Deferred".to_string(),
+                    };
+                    children.push(TreeItem::new(
+                        label,
+                        block.node.as_deref().map(Node::from).into_iter().collect(),
+                    ));
+                }
+                TreeItem {
+                    label: "Defer".to_string(),
+                    subgraph: None,
+                    children,
+                }
+            }
+            Node::Condition(condition) => {
+                let mut children = Vec::new();
+                if let Some(if_clause) = condition.if_clause.as_deref() {
+                    children.push(TreeItem::new("If", vec![Node::from(if_clause)]));
+                }
+                if let Some(else_clause) = condition.else_clause.as_deref() {
+                    children.push(TreeItem::new("Else", vec![Node::from(else_clause)]));
+                }
+                TreeItem {
+                    label: format!("Condition(if: ${})", condition.condition_variable),
+                    subgraph: None,
+                    children,
+                }
+            }
+        }
+    }
+}
+
+fn write_tree_items(
+    lines: &mut Vec<String>,
+    items: &[TreeItem],
+    prefix: &str,
+    indent: &TreeIndent,
+) {
+    for (i, item) in items.iter().enumerate() {
+        let is_last = i + 1 == items.len();
+        let (connector, continuation) = if is_last {
+            (&indent.last_connector, &indent.last_continuation)
+        } else {
+            (&indent.connector, &indent.continuation)
+        };
+        lines.push(format!("{prefix}{connector}{}", item.label));
+        write_tree_items(
+            lines,
+            &item.children,
+            &format!("{prefix}{continuation}"),
+            indent,
+        );
+    }
+}
+
+/// Renders the query plan on a single line in a bracketed notation.
+/// - For example, `Sequence[Fetch(accounts), Parallel[Fetch(reviews), Fetch(products)]]`.
+/// - Fetches show the subgraph they are sent to, and flattens their path. An empty plan is
+///   rendered as `QueryPlan[]`.
+pub fn plan_to_compact(plan: &QueryPlan) -> String {
+    match Node::root(plan) {
+        Some(root) => compact_node(root),
+        None => "QueryPlan[]".to_string(),
+    }
+}
+
+fn compact_node(node: Node<'_>) -> String {
+    let nested = |label: &str, children: Vec<Node<'_>>| {
+        let children: Vec<_> = children.into_iter().map(compact_node).collect();
+        format!("{label}[{}]", children.join(", "))
+    };
+    match node {
+        Node::Fetch(fetch) => format!("Fetch({})", fetch.subgraph_name),
+        Node::Sequence(_) => nested("Sequence", node.children()),
+        Node::Parallel(_) => nested("Parallel", node.children()),
+        Node::Flatten(flatten) => nested(
+            &format!("Flatten({})", display_path(&flatten.path)),
+            node.children(),
+        ),
+        Node::Subscription(_) => nested("Subscription", node.children()),
+        Node::Defer(defer) => {
+            let mut blocks = vec![nested(
+                "Primary",
+                defer
+                    .primary
+                    .node
+                    .as_deref()
+                    .map(Node::from)
+                    .into_iter()
+                    .collect(),
+            )];
+            for block in &defer.deferred {
+                let label = match &block.label {
+                    Some(label) => format!("Deferred({label})"),
+                    None => "Deferred".to_string(),
+                };
+                blocks.push(nested(
+                    &label,
+                    block.node.as_deref().map(Node::from).into_iter().collect(),
+                ));
+            }
+            format!("Defer[{}]", blocks.join(", "))
+        }
+        Node::Condition(condition) => {
+            let mut branches = Vec::new();
+            if let Some(if_clause) = condition.if_clause.as_deref() {
+                branches.push(nested("If", vec![Node::from(if_clause)]));
+            }
+            if let Some(else_clause) = condition.else_clause.as_deref() {
+                branches.push(nested("Else", vec![Node::from(else_clause)]));
+            }
+            format!(
+                "Condition(${})[{}]",
+                condition.condition_variable,
+                branches.join(", ")
+            )
+        }
+    }
+}
+
+/// Renders the query plan as a GraphViz DOT digraph.
+/// - Fetch nodes are filled with a color per subgraph, and a legend lists the subgraphs' colors.
+/// - Colors are derived from a hash of the subgraph name, so that a subgraph always gets the same
+///   color across runs and query plans.
+pub fn plan_to_dot(plan: &QueryPlan) -> String {
+    let mut lines = vec![
+        "digraph QueryPlan {".to_string(),
+        "  node [shape=box, fontname=\"Helvetica\"];".to_string(),
+        "  n0 [label=\"QueryPlan\"];".to_string(),
+    ];
+    let mut subgraphs = BTreeSet::new();
+    if let Some(root) = Node::root(plan) {
+        let mut next_id = 1;
+        write_dot_item(
+            &mut lines,
+            &TreeItem::from_node(root),
+            0,
+            &mut next_id,
+            &mut subgraphs,
+        );
+    }
+    if !subgraphs.is_empty() {
+        lines.push("  subgraph cluster_legend {".to_string());
+        lines.push("    label=\"Subgraphs\";".to_string());
+        for (i, subgraph) in subgraphs.iter().enumerate() {
+            lines.push(format!(
+                "    legend{i} [label=\"{}\", style=filled, fillcolor=\"{}\"];",
+                escape_dot(subgraph),
+                subgraph_color(subgraph)
+            ));
+        }
+        lines.push("  }".to_string());
+    }
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+fn write_dot_item<'a>(
+    lines: &mut Vec<String>,
+    item: &'a TreeItem,
+    parent_id: usize,
+    next_id: &mut usize,
+    subgraphs: &mut BTreeSet<&'a str>,
+) {
+    let id = *next_id;
+    *next_id += 1;
+    let style = match &item.subgraph {
+        Some(subgraph) => {
+            subgraphs.insert(subgraph);
+            format!(", style=filled, fillcolor=\"{}\"", subgraph_color(subgraph))
+        }
+        None => String::new(),
+    };
+    lines.push(format!(
+        "  n{id} [label=\"{}\"{style}];",
+        escape_dot(&item.label)
+    ));
+    lines.push(format!("  n{parent_id} -> n{id};"));
+    for child in &item.children {
+        write_dot_item(lines, child, id, next_id, subgraphs);
+    }
+}
+
+/// Renders the query plan as a Mermaid flowchart.
+/// - If `node_link` is given, fetch nodes link to it, with `{subgraph}` replaced by the name of the
+///   subgraph fetched from (e.g. `https://example.com/subgraphs/{subgraph}`).
+pub fn plan_to_mermaid(plan: &QueryPlan, node_link: Option<&str>) -> String {
+    let mut lines = vec![
+        "flowchart TD".to_string(),
+        "  n0[\"QueryPlan\"]".to_string(),
+    ];
+    if let Some(root) = Node::root(plan) {
+        let mut next_id = 1;
+        write_mermaid_item(
+            &mut lines,
+            &TreeItem::from_node(root),
+            0,
+            &mut next_id,
+            node_link,
+        );
+    }
+    lines.join("\n")
+}
+
+fn write_mermaid_item(
+    lines: &mut Vec<String>,
+    item: &TreeItem,
+    parent_id: usize,
+    next_id: &mut usize,
+    node_link: Option<&str>,
+) {
+    let id = *next_id;
+    *next_id += 1;
+    lines.push(format!("  n{id}[\"{}\"]", escape_mermaid(&item.label)));
+    lines.push(format!("  n{parent_id} --> n{id}"));
+    if let (Some(subgraph), Some(node_link)) = (&item.subgraph, node_link) {
+        let url = node_link.replace("{subgraph}", subgraph);
+        lines.push(format!(
+            "  click n{id} href \"{}\" _blank",
+            escape_mermaid(&url)
+        ));
+    }
+    for child in &item.children {
+        write_mermaid_item(lines, child, id, next_id, node_link);
+    }
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+/// A light color (as a GraphViz HSV triple) derived from a hash of the subgraph name.
+fn subgraph_color(subgraph: &str) -> String {
+    let digest = short_digest(subgraph);
+    let hue = u16::from_str_radix(&digest[..4], 16).unwrap_or(0);
+    format!("{:.3} 0.35 0.95", f64::from(hue) / f64::from(u16::MAX))
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Formats a flatten path the same way as the query plan display (e.g. `a.@.b`).
+pub(crate) fn display_path<T: Display>(path: &[T]) -> String {
+    path.iter()
+        .map(|element| element.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
@@ -0,0 +1,83 @@
+use apollo_federation::query_plan::QueryPlan;
+use serde_json::Map;
+use serde_json::Value;
+
+/// The plan node kinds, as tagged in both the federation and the router serializations
+const NODE_KINDS: [&str; 7] = [
+    "Fetch",
+    "Sequence",
+    "Parallel",
+    "Flatten",
+    "Defer",
+    "Condition",
+    "Subscription",
+];
+
+/// Converts the query plan to the JSON shape Apollo Router logs and exposes for query plans.
+/// - Plan nodes are internally tagged with `kind`, and fields are in camelCase with the router's
+///   names (e.g. `serviceName`, `operation`, `ifClause`).
+/// - Fields computed by the router itself (`schemaAwareHash`, `authorization`) are not
+///   reproduced, and the planning statistics are left out.
+pub fn plan_to_router_json(plan: &QueryPlan) -> Value {
+    let node = match serde_json::to_value(&plan.node) {
+        Ok(node) => to_router_shape(node),
+        Err(e) => Value::String(format!("(failed to serialize the query plan: {e})")),
+    };
+    let mut object = Map::new();
+    object.insert("kind".to_string(), Value::from("QueryPlan"));
+    object.insert("node".to_string(), node);
+    Value::Object(object)
+}
+
+fn to_router_shape(value: Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            // Externally tagged plan nodes (`{"Fetch": {...}}`) become internally tagged.
+            if object.len() == 1
+                && let Some((kind, Value::Object(fields))) = object.iter().next()
+                && NODE_KINDS.contains(&kind.as_str())
+            {
+                let mut node = Map::new();
+                node.insert("kind".to_string(), Value::from(kind.as_str()));
+                node.extend(convert_fields(fields.clone()));
+                return Value::Object(node);
+            }
+            Value::Object(convert_fields(object))
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(to_router_shape).collect()),
+        other => other,
+    }
+}
+
+fn convert_fields(fields: Map<String, Value>) -> Map<String, Value> {
+    fields
+        .into_iter()
+        .map(|(name, value)| (router_field_name(&name), to_router_shape(value)))
+        .collect()
+}
+
+fn router_field_name(name: &str) -> String {
+    match name {
+        "subgraph_name" => "serviceName".to_string(),
+        "operation_document" => "operation".to_string(),
+        "condition_variable" => "condition".to_string(),
+        "sub_selection" => "subselection".to_string(),
+        _ => camel_case(name),
+    }
+}
+
+fn camel_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut upper_next = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper_next = true;
+        } else if upper_next {
+            result.extend(c.to_uppercase());
+            upper_next = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
@@ -0,0 +1,177 @@
+use apollo_federation::query_plan::QueryPlan;
+
+use crate::QueryPlanResult;
+use crate::node::Node;
+use crate::render::display_path;
+
+/// Structural statistics of a query plan.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct QueryPlanStats {
+    /// The number of fetches sent to subgraphs
+    pub fetch_count: usize,
+    /// The number of entity fetches (fetches that take representations as input)
+    pub entity_fetch_count: usize,
+    /// The number of fetches on the critical path (fetches that have to run one after another)
+    /// - See `critical_path_length` for the traversal semantics.
+    pub critical_path_length: usize,
+    /// The nesting depth of the plan nodes (the number of nodes on the longest path from the root)
+    pub max_depth: usize,
+    /// The number of selections required as input by entity fetches, summed over all fetches
+    pub requires_count: usize,
+    /// The maximum number of branches of a `Parallel` node (0, if there is no `Parallel` node)
+    pub max_fan_out: usize,
+    /// Whether the query plan may resolve entities per list item at runtime (a heuristic)
+    /// - Set if an entity fetch is nested under a `Flatten` over a list path (like `a.@.b`).
+    pub possible_n_plus_one: bool,
+    /// Why `possible_n_plus_one` is set (for the first such fetch)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n_plus_one_reason: Option<String>,
+}
+
+/// Returns the structural statistics of the query plan.
+pub fn plan_stats(plan: &QueryPlan) -> QueryPlanStats {
+    let mut stats = QueryPlanStats::default();
+    let Some(root) = Node::root(plan) else {
+        return stats;
+    };
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if let Node::Fetch(fetch) = node {
+            stats.fetch_count += 1;
+            if !fetch.requires.is_empty() {
+                stats.entity_fetch_count += 1;
+            }
+            stats.requires_count += fetch.requires.len();
+        }
+        if let Node::Parallel(branches) = node {
+            stats.max_fan_out = stats.max_fan_out.max(branches.len());
+        }
+        stack.extend(node.children());
+    }
+    stats.critical_path_length = critical_path_length(root);
+    stats.max_depth = max_depth(root);
+    stats.n_plus_one_reason = n_plus_one_reason(root, None);
+    stats.possible_n_plus_one = stats.n_plus_one_reason.is_some();
+    stats
+}
+
+/// Finds an entity fetch nested under a `Flatten` over a list path, and describes it.
+/// - `list_path` is the path of the innermost enclosing `Flatten` over a list path, if any.
+fn n_plus_one_reason(node: Node<'_>, list_path: Option<&str>) -> Option<String> {
+    match node {
+        Node::Fetch(fetch) if !fetch.requires.is_empty() => list_path.map(|path| {
+            format!(
+                "entity fetch from subgraph \"{}\" is flattened over the list path \"{path}\"",
+                fetch.subgraph_name
+            )
+        }),
+        Node::Flatten(flatten) => {
+            let path = display_path(&flatten.path);
+            let is_list_path = flatten
+                .path
+                .iter()
+                .any(|element| element.to_string().starts_with('@'));
+            let list_path = if is_list_path {
+                Some(path.as_str())
+            } else {
+                list_path
+            };
+            n_plus_one_reason(Node::from(&*flatten.node), list_path)
+        }
+        _ => node
+            .children()
+            .into_iter()
+            .find_map(|child| n_plus_one_reason(child, list_path)),
+    }
+}
+
+/// The number of nodes on the longest path from the node to a leaf of the plan tree.
+fn max_depth(node: Node<'_>) -> usize {
+    1 + node
+        .children()
+        .into_iter()
+        .map(max_depth)
+        .max()
+        .unwrap_or(0)
+}
+
+/// The number of fetches on the longest chain of fetches that run one after another.
+/// - A fetch counts as 1, a `Sequence` sums its children, and `Parallel` takes the maximum of its
+///   branches. A `Condition` takes the maximum of its branches, since either may run.
+/// - A `Flatten` counts as its child, a `Subscription` as its primary fetch plus the rest, and a
+///   `Defer` as its primary block plus the longest deferred block.
+fn critical_path_length(node: Node<'_>) -> usize {
+    match node {
+        Node::Fetch(_) => 1,
+        Node::Sequence(_) => node.children().into_iter().map(critical_path_length).sum(),
+        Node::Parallel(_) | Node::Condition(_) => node
+            .children()
+            .into_iter()
+            .map(critical_path_length)
+            .max()
+            .unwrap_or(0),
+        Node::Flatten(flatten) => critical_path_length(Node::from(&*flatten.node)),
+        Node::Subscription(subscription) => {
+            1 + subscription
+                .rest
+                .as_deref()
+                .map_or(0, |rest| critical_path_length(Node::from(rest)))
+        }
+        // Deferred blocks start after the primary block.
+        Node::Defer(defer) => {
+            let primary = defer
+                .primary
+                .node
+                .as_deref()
+                .map_or(0, |node| critical_path_length(Node::from(node)));
+            let deferred = defer
+                .deferred
+                .iter()
+                .filter_map(|block| block.node.as_deref())
+                .map(|node| critical_path_length(Node::from(node)))
+                .max()
+                .unwrap_or(0);
+            primary + deferred
+        }
+    }
+}
+
+/// Weights of the query plan statistics in the cost of a query plan.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CostWeights {
+    /// The cost of each fetch
+    pub fetch: f64,
+    /// The additional cost of each entity fetch
+    pub entity_fetch: f64,
+    /// The cost of each fetch on the critical path (see `QueryPlanStats::critical_path_length`)
+    pub depth: f64,
+    /// The cost of each selection required by entity fetches
+    pub requires: f64,
+}
+
+impl Default for CostWeights {
+    fn default() -> Self {
+        CostWeights {
+            fetch: 1.0,
+            entity_fetch: 2.0,
+            depth: 3.0,
+            requires: 0.5,
+        }
+    }
+}
+
+/// Computes the cost of a query plan as the weighted sum of its statistics.
+/// - The cost is a relative measure to compare query plans of the same query.
+pub fn plan_cost(stats: &QueryPlanStats, weights: &CostWeights) -> f64 {
+    stats.fetch_count as f64 * weights.fetch
+        + stats.entity_fetch_count as f64 * weights.entity_fetch
+        + stats.critical_path_length as f64 * weights.depth
+        + stats.requires_count as f64 * weights.requires
+}
+
+impl QueryPlanResult {
+    /// Returns the structural statistics of the query plan (see `plan_stats`).
+    pub fn stats(&self) -> QueryPlanStats {
+        plan_stats(&self.experimental_query_plan_serialized)
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::BTreeSet;
+
+use apollo_federation::query_plan::QueryPlan;
+
+use crate::AnalyzerError;
+use crate::QueryPlanResult;
+use crate::Result;
+use crate::load_supergraph;
+use crate::node::Node;
+
+/// Returns the names of the subgraphs fetched from by the query plan, in alphabetical order.
+pub fn plan_subgraphs(plan: &QueryPlan) -> BTreeSet<String> {
+    let mut subgraphs = BTreeSet::new();
+    let mut stack: Vec<_> = Node::root(plan).into_iter().collect();
+    while let Some(node) = stack.pop() {
+        if let Node::Fetch(fetch) = node {
+            subgraphs.insert(fetch.subgraph_name.to_string());
+        }
+        stack.extend(node.children());
+    }
+    subgraphs
+}
+
+impl QueryPlanResult {
+    /// Returns the subgraphs fetched from by the query plan (see `plan_subgraphs`).
+    pub fn subgraphs(&self) -> BTreeSet<String> {
+        plan_subgraphs(&self.experimental_query_plan_serialized)
+    }
+}
+
+/// A subgraph of the supergraph schema.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SubgraphInfo {
+    /// The subgraph name (as referred to by the fetch nodes of query plans)
+    pub name: String,
+    /// The routing URL of the subgraph, if the supergraph schema specifies one
+    pub url: Option<String>,
+}
+
+/// Lists the subgraphs composed into the supergraph schema, in alphabetical order.
+pub fn list_subgraphs(schema_str: &str) -> Result<Vec<SubgraphInfo>> {
+    let subgraphs = load_supergraph(schema_str)?
+        .extract_subgraphs()
+        .map_err(AnalyzerError::SchemaComposition)?;
+    Ok(subgraphs
+        .into_iter()
+        .map(|(name, subgraph)| SubgraphInfo {
+            name: name.to_string(),
+            // Note: Subgraphs without a routing URL are extracted with an empty one.
+            url: (!subgraph.url.is_empty()).then_some(subgraph.url),
+        })
+        .collect())
+}
+
+/// Returns the schema of a subgraph, as extracted from the supergraph schema, as SDL.
+/// - Fails with the names of the available subgraphs, if there is no subgraph of that name.
+pub fn subgraph_sdl(schema_str: &str, subgraph_name: &str) -> Result<String> {
+    let subgraphs = load_supergraph(schema_str)?
+        .extract_subgraphs()
+        .map_err(AnalyzerError::SchemaComposition)?;
+    let Some(subgraph) = subgraphs.get(subgraph_name) else {
+        return Err(AnalyzerError::UnknownSubgraph {
+            name: subgraph_name.to_string(),
+            available: subgraphs
+                .into_iter()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+        });
+    };
+    Ok(subgraph.schema.schema().to_string())
+}
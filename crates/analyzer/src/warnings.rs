@@ -0,0 +1,68 @@
+use apollo_compiler::ExecutableDocument;
+use apollo_compiler::ast::DirectiveList;
+use apollo_compiler::collections::IndexSet;
+use apollo_compiler::executable::Selection;
+use apollo_compiler::executable::SelectionSet;
+
+/// The reason of `@deprecated` directives without one (as defined by the GraphQL specification)
+const DEFAULT_DEPRECATION_REASON: &str = "No longer supported";
+
+/// Collects the non-fatal issues of a valid query document, which don't prevent planning it.
+/// - Currently, the selections of deprecated fields and the uses of deprecated arguments
+///   (`@deprecated` in the API schema).
+/// - Every operation and fragment of the document is checked. Each issue is listed once, in
+///   document order.
+pub(crate) fn query_warnings(query_doc: &ExecutableDocument) -> Vec<String> {
+    let mut warnings = IndexSet::default();
+    for operation in query_doc.operations.iter() {
+        collect_deprecations(&operation.selection_set, &mut warnings);
+    }
+    for fragment in query_doc.fragments.values() {
+        collect_deprecations(&fragment.selection_set, &mut warnings);
+    }
+    warnings.into_iter().collect()
+}
+
+fn collect_deprecations(selection_set: &SelectionSet, warnings: &mut IndexSet<String>) {
+    for selection in &selection_set.selections {
+        match selection {
+            Selection::Field(field) => {
+                if let Some(reason) = deprecation_reason(&field.definition.directives) {
+                    warnings.insert(format!(
+                        "Field `{}.{}` is deprecated: {reason}",
+                        selection_set.ty, field.name
+                    ));
+                }
+                for argument in &field.arguments {
+                    let reason = field
+                        .definition
+                        .argument_by_name(&argument.name)
+                        .and_then(|definition| deprecation_reason(&definition.directives));
+                    if let Some(reason) = reason {
+                        warnings.insert(format!(
+                            "Argument `{}.{}({}:)` is deprecated: {reason}",
+                            selection_set.ty, field.name, argument.name
+                        ));
+                    }
+                }
+                collect_deprecations(&field.selection_set, warnings);
+            }
+            Selection::InlineFragment(inline_fragment) => {
+                collect_deprecations(&inline_fragment.selection_set, warnings);
+            }
+            // Fragment definitions are checked on their own.
+            Selection::FragmentSpread(_) => {}
+        }
+    }
+}
+
+/// Returns the deprecation reason, if the directives include `@deprecated`.
+fn deprecation_reason(directives: &DirectiveList) -> Option<&str> {
+    let deprecated = directives.get("deprecated")?;
+    Some(
+        deprecated
+            .specified_argument_by_name("reason")
+            .and_then(|reason| reason.as_str())
+            .unwrap_or(DEFAULT_DEPRECATION_REASON),
+    )
+}
@@ -0,0 +1,25 @@
+use anyhow::Error as AnyError;
+use anyhow::anyhow;
+use std::path::Path;
+
+use crate::input::read_input;
+
+/// The query planner options of a config file, in TOML (like `QueryPlannerArgs`)
+/// - The options are the defaults of the command line options of the same names. Options left out
+///   of the file fall back to the built-in defaults.
+#[derive(Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct PlannerConfigFile {
+    pub(crate) disable_generate_query_fragments: Option<bool>,
+    pub(crate) disable_defer_support: Option<bool>,
+    pub(crate) experimental_type_conditioned_fetching: Option<bool>,
+    pub(crate) experimental_plans_limit: Option<u32>,
+    pub(crate) experimental_paths_limit: Option<u32>,
+}
+
+/// Reads the config file, whose options become the defaults of the query planner options
+/// (see `QueryPlannerArgs::to_config`).
+pub(crate) fn load_config_file(path: &Path) -> Result<PlannerConfigFile, AnyError> {
+    let text = read_input(path)?;
+    toml::from_str(&text).map_err(|e| anyhow!("Invalid config file {}: {e}", path.display()))
+}
@@ -0,0 +1,41 @@
+use anyhow::Error as AnyError;
+use apollo_compiler::validation::DiagnosticList;
+use qp_analyzer::AnalyzerError;
+use std::fmt;
+
+/// An invalid query document, with the diagnostics of its parsing and validation
+#[derive(Debug)]
+pub(crate) struct InvalidQuery {
+    diagnostics: DiagnosticList,
+}
+
+impl InvalidQuery {
+    /// Renders the diagnostics in the compiler style: each message with the offending source
+    /// line(s) and the problem underlined.
+    /// - The diagnostics are colored if `color` is set (see `ColorChoice` for how it is decided).
+    pub(crate) fn render(&self, color: bool) -> String {
+        if color {
+            // The `Debug` format of diagnostics is the colored one.
+            format!("{:?}", self.diagnostics)
+        } else {
+            self.diagnostics.to_string()
+        }
+    }
+}
+
+impl fmt::Display for InvalidQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+impl std::error::Error for InvalidQuery {}
+
+/// Replaces the error of an invalid query with the diagnostics of the query (rendered with its
+/// source). Other errors are returned as they are.
+pub(crate) fn with_query_diagnostics(error: AnalyzerError) -> AnyError {
+    match error {
+        AnalyzerError::QueryValidation { diagnostics, .. } => InvalidQuery { diagnostics }.into(),
+        error => error.into(),
+    }
+}
@@ -0,0 +1,226 @@
+use anyhow::Error as AnyError;
+use anyhow::anyhow;
+use flate2::read::GzDecoder;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// The first bytes of any gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The timeout of fetching inputs from URLs (set once from the command line)
+static FETCH_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+const DEFAULT_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub(crate) fn set_fetch_timeout(timeout: Duration) {
+    // Note: Only the first call takes effect, which is the one from `main`.
+    let _ = FETCH_TIMEOUT.set(timeout);
+}
+
+/// Reads an input file, stdin if the path is `-`, or fetches it if the path is an HTTP(S) URL or
+/// a GraphOS graph reference (`graphref:<graph>@<variant>`).
+/// - Gzip-compressed inputs (detected by their magic bytes) are decompressed transparently.
+pub(crate) fn read_input(input_path: &Path) -> Result<String, AnyError> {
+    let describe = || {
+        if is_stdin(input_path) {
+            "stdin".to_string()
+        } else {
+            input_path.display().to_string()
+        }
+    };
+    let bytes = if let Some(graph_ref) = as_graph_ref(input_path) {
+        fetch_graph_ref(graph_ref)?
+    } else if let Some(url) = as_url(input_path) {
+        fetch_url(url)?
+    } else if is_stdin(input_path) {
+        let mut bytes = Vec::new();
+        io::stdin()
+            .read_to_end(&mut bytes)
+            .map(|_| bytes)
+            .map_err(|e| anyhow!("Failed to read {}: {e}", describe()))?
+    } else {
+        fs::read(input_path).map_err(|e| anyhow!("Failed to read {}: {e}", describe()))?
+    };
+
+    let bytes = if bytes.starts_with(&GZIP_MAGIC) {
+        let mut decompressed = Vec::new();
+        GzDecoder::new(bytes.as_slice())
+            .read_to_end(&mut decompressed)
+            .map_err(|e| anyhow!("Failed to decompress {}: {e}", describe()))?;
+        decompressed
+    } else {
+        bytes
+    };
+    String::from_utf8(bytes).map_err(|e| anyhow!("{} is not valid UTF-8: {e}", describe()))
+}
+
+/// Reads several input files, of which at most one can be stdin (`-`).
+pub(crate) fn read_inputs<const N: usize>(
+    input_paths: [&Path; N],
+) -> Result<[String; N], AnyError> {
+    let inputs = read_input_list(&input_paths)?;
+    Ok(inputs
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("one input is read per path")))
+}
+
+/// Same as `read_inputs`, for any number of input files.
+pub(crate) fn read_input_list(input_paths: &[&Path]) -> Result<Vec<String>, AnyError> {
+    if input_paths.iter().filter(|path| is_stdin(path)).count() > 1 {
+        return Err(anyhow!(
+            "Only one of the input files can be read from stdin (`-`): {}",
+            input_paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    input_paths.iter().map(|path| read_input(path)).collect()
+}
+
+/// Expands directories into the query files they contain (recursively, in path order).
+/// - Files in directories are query files if their extension is `.graphql` or `.gql`. Paths that
+///   are not directories are kept as they are.
+pub(crate) fn expand_query_paths(input_paths: &[PathBuf]) -> Result<Vec<PathBuf>, AnyError> {
+    let mut query_paths = Vec::new();
+    for input_path in input_paths {
+        if input_path.is_dir() {
+            let mut dir_paths = Vec::new();
+            collect_query_files(input_path, &mut dir_paths)?;
+            dir_paths.sort();
+            query_paths.extend(dir_paths);
+        } else {
+            query_paths.push(input_path.clone());
+        }
+    }
+    Ok(query_paths)
+}
+
+fn collect_query_files(dir: &Path, query_paths: &mut Vec<PathBuf>) -> Result<(), AnyError> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| anyhow!("Failed to read {}: {e}", dir.display()))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|e| anyhow!("Failed to read {}: {e}", dir.display()))?
+            .path();
+        if path.is_dir() {
+            collect_query_files(&path, query_paths)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "graphql" || extension == "gql")
+        {
+            query_paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the name of an input in diagnostics: its path, or `<stdin>`.
+pub(crate) fn source_name(input_path: &Path) -> &Path {
+    if is_stdin(input_path) {
+        Path::new("<stdin>")
+    } else {
+        input_path
+    }
+}
+
+fn is_stdin(input_path: &Path) -> bool {
+    input_path == Path::new("-")
+}
+
+/// Returns the path as a URL, if it is an `http://` or `https://` URL.
+fn as_url(input_path: &Path) -> Option<&str> {
+    let path = input_path.to_str()?;
+    (path.starts_with("http://") || path.starts_with("https://")).then_some(path)
+}
+
+fn http_client() -> Result<reqwest::blocking::Client, AnyError> {
+    let timeout = *FETCH_TIMEOUT.get().unwrap_or(&DEFAULT_FETCH_TIMEOUT);
+    reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| anyhow!("Failed to create an HTTP client: {e}"))
+}
+
+fn fetch_url(url: &str) -> Result<Vec<u8>, AnyError> {
+    let response = http_client()?
+        .get(url)
+        .send()
+        .map_err(|e| anyhow!("Failed to fetch {url}: {e}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow!("Failed to fetch {url}: HTTP status {status}"));
+    }
+    let body = response
+        .bytes()
+        .map_err(|e| anyhow!("Failed to fetch {url}: {e}"))?;
+    Ok(body.to_vec())
+}
+
+/// Returns the graph reference, if the path is like `graphref:<graph>@<variant>`.
+fn as_graph_ref(input_path: &Path) -> Option<&str> {
+    input_path.to_str()?.strip_prefix("graphref:")
+}
+
+/// The Apollo Uplink endpoint serving the supergraph schemas of GraphOS graphs
+#[cfg(feature = "graphos")]
+const UPLINK_URL: &str = "https://uplink.api.apollographql.com/";
+
+/// The Apollo Uplink query fetching the supergraph schema of a graph variant (as Apollo Router does)
+#[cfg(feature = "graphos")]
+const SUPERGRAPH_QUERY: &str = r#"query SupergraphSdl($apiKey: String!, $graphRef: String!) {
+  routerConfig(ref: $graphRef, apiKey: $apiKey) {
+    __typename
+    ... on RouterConfigResult { supergraphSdl: supergraphSDL }
+    ... on FetchError { code message }
+  }
+}"#;
+
+/// Fetches the supergraph schema of the graph variant from GraphOS, using the `APOLLO_KEY` API key.
+#[cfg(feature = "graphos")]
+fn fetch_graph_ref(graph_ref: &str) -> Result<Vec<u8>, AnyError> {
+    let api_key = std::env::var("APOLLO_KEY").map_err(|_| {
+        anyhow!("The `APOLLO_KEY` environment variable is required for {graph_ref}")
+    })?;
+    let request = serde_json::json!({
+        "query": SUPERGRAPH_QUERY,
+        "variables": { "apiKey": api_key, "graphRef": graph_ref },
+    });
+    let response = http_client()?
+        .post(UPLINK_URL)
+        .json(&request)
+        .send()
+        .map_err(|e| anyhow!("Failed to fetch {graph_ref} from GraphOS: {e}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow!(
+            "Failed to fetch {graph_ref} from GraphOS: HTTP status {status}"
+        ));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .map_err(|e| anyhow!("Invalid response from GraphOS for {graph_ref}: {e}"))?;
+    let config = &body["data"]["routerConfig"];
+    match config["supergraphSdl"].as_str() {
+        Some(sdl) => Ok(sdl.as_bytes().to_vec()),
+        None => Err(anyhow!(
+            "Failed to fetch {graph_ref} from GraphOS: {}",
+            config["message"]
+                .as_str()
+                .map_or_else(|| body.to_string(), str::to_string)
+        )),
+    }
+}
+
+#[cfg(not(feature = "graphos"))]
+fn fetch_graph_ref(graph_ref: &str) -> Result<Vec<u8>, AnyError> {
+    Err(anyhow!(
+        "Loading {graph_ref} from GraphOS requires building with the `graphos` feature"
+    ))
+}
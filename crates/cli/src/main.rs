@@ -4,50 +4,333 @@ use apollo_federation::query_plan::query_planner::QueryPlanIncrementalDeliveryCo
 use apollo_federation::query_plan::query_planner::QueryPlannerConfig;
 use apollo_federation::query_plan::query_planner::QueryPlannerDebugConfig;
 use clap::Parser;
+use clap::builder::BoolishValueParser;
+use qp_analyzer::Analyzer;
+use qp_analyzer::CombinationFailure;
+use qp_analyzer::CostWeights;
+use qp_analyzer::DEFAULT_TREE_INDENT;
+use qp_analyzer::EnumerationMode;
+use qp_analyzer::OverrideCombinations;
+use qp_analyzer::OverrideTarget;
+use qp_analyzer::PlanChanges;
 use qp_analyzer::QueryPlanDifference;
 use qp_analyzer::QueryPlanResult;
+use qp_analyzer::TreeOptions;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt;
 use std::fs;
-use std::io;
+use std::io::IsTerminal;
 use std::num::NonZeroU32;
+use std::num::NonZeroUsize;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::prelude::*;
 
+mod config_file;
+mod diagnostics;
+mod input;
+mod manifest;
+mod metrics;
+mod operations;
+#[macro_use]
+mod output;
+mod redact;
+mod sqlite;
+mod summary;
+
+use config_file::PlannerConfigFile;
+use diagnostics::InvalidQuery;
+use diagnostics::with_query_diagnostics;
+use input::read_input;
+use input::read_inputs;
+use input::source_name;
+use manifest::parse_manifest;
+use metrics::RunMetrics;
+use operations::OperationFailure;
+use operations::OperationSet;
+use operations::operation_display_name;
+use operations::read_operation_set;
+use redact::REDACTED;
+use sqlite::SqliteWriter;
+use summary::PlanStats;
+use summary::PlanSummary;
+use summary::SubgraphChanges;
+
+use qp_analyzer::annotate_display_subgraphs;
+use qp_analyzer::api_schema_sdl;
 use qp_analyzer::build_all_plans;
 use qp_analyzer::build_one_plan;
+use qp_analyzer::build_one_plan_with_states;
+use qp_analyzer::combination_name;
+use qp_analyzer::describe_override_labels;
 use qp_analyzer::get_override_labels;
+use qp_analyzer::insensitive_override_labels;
+use qp_analyzer::list_operations;
+use qp_analyzer::list_subgraphs;
+use qp_analyzer::minimal_override_set_for;
+use qp_analyzer::operation_names;
+use qp_analyzer::override_label_percentage;
+use qp_analyzer::plan_cost;
+use qp_analyzer::plan_to_compact;
+use qp_analyzer::plan_to_dot;
+use qp_analyzer::plan_to_identified_nodes;
+use qp_analyzer::plan_to_mermaid;
+use qp_analyzer::plan_to_router_json;
+use qp_analyzer::plan_to_tree_with_options;
+use qp_analyzer::subgraph_sdl;
 
 #[derive(clap::Parser)]
+struct Cli {
+    /// Timeout of fetching schema and query files from URLs, in seconds.
+    #[arg(long, global = true, value_name = "SECONDS", default_value_t = 30)]
+    fetch_timeout: u64,
+
+    /// Maximum number of queries or operations planned concurrently (the number of CPUs by
+    /// default).
+    #[arg(long, short, global = true, value_name = "N")]
+    jobs: Option<NonZeroUsize>,
+
+    /// Format of the log output.
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        env = "QP_LOG_FORMAT",
+        default_value_t = LogFormat::Text
+    )]
+    log_format: LogFormat,
+
+    /// Log level, unless the `RUST_LOG` environment variable is set (which takes precedence).
+    #[arg(long, global = true, value_enum)]
+    log_level: Option<LogLevel>,
+
+    /// When to color the diagnostics of invalid queries (`auto` respects `NO_COLOR`).
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Write the output of the command to this file (created or truncated) instead of stdout.
+    #[arg(long, short, global = true, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Replace the subgraph routing URLs of the supergraph schema in the output with `<redacted>`.
+    #[arg(long, global = true)]
+    redact_urls: bool,
+
+    /// TOML file of query planner options, used as defaults of the command line options.
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Subcommand)]
 enum Command {
     /// List all override condition labels in supergraph schema
     ListOverrides {
-        /// Path to the supergraph schema file.
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
         schema: PathBuf,
+        /// Also print the rollout percentage of each label and the fields it applies to.
+        #[arg(long)]
+        describe: bool,
     },
-    /// Plan all possible query plans for supergraph schema and query
-    Plan {
-        /// Path to the supergraph schema file.
+    /// Export the fields each override condition label applies to
+    ExportOverrides {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
+        schema: PathBuf,
+        /// Output the labels and their fields in JSON format (tab-separated text otherwise).
+        #[arg(long)]
+        json: bool,
+    },
+    /// Count the override condition labels and their combinations (2^n for n labels)
+    #[command(alias = "count")]
+    CountCombinations {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
+        schema: PathBuf,
+        /// Only print the number of combinations.
+        #[arg(long, short)]
+        quiet: bool,
+    },
+    /// List the subgraphs composed into the supergraph schema
+    ListSubgraphs {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
+        schema: PathBuf,
+        /// Also print the routing URL of each subgraph (`name -> url`).
+        #[arg(long)]
+        urls: bool,
+        /// Output the subgraph names in JSON format (an object mapping names to routing URLs, with
+        /// `--urls`).
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the schema of a subgraph, as extracted from the supergraph schema, as SDL
+    SubgraphSdl {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
+        schema: PathBuf,
+        /// The subgraph name (see `list-subgraphs`).
+        name: String,
+    },
+    /// List the operations of a query file, with their types
+    ListOperations {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
         schema: PathBuf,
         /// Path to the query file, `-` for stdin.
         query: PathBuf,
+    },
+    /// Plan all possible query plans for supergraph schema and query
+    Plan(PlanArgs),
+    /// Plan a query plan for supergraph schema, query and override conditions
+    PlanOne(PlanOneArgs),
+    /// Plan all possible query plans for every operation of a persisted query manifest
+    PlanManifest {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
+        schema: PathBuf,
+        /// Path to the persisted query manifest (JSON), `-` for stdin.
+        manifest: PathBuf,
         /// Output results in JSON format.
         #[arg(long)]
         json: bool,
+        /// Which combinations of override conditions to plan.
+        #[arg(long, value_enum, default_value_t = Enumeration::All)]
+        enumerate: Enumeration,
+        /// Also write a row for each query plan result to the `query_plans` table of this SQLite
+        /// database (requires the `sqlite` feature).
+        #[arg(long, value_name = "PATH")]
+        sqlite: Option<PathBuf>,
+        /// Write Prometheus text-format metrics of the run to this file.
+        #[arg(long, value_name = "PATH")]
+        metrics: Option<PathBuf>,
         /// Query planner arguments
         #[command(flatten)]
         planner_args: QueryPlannerArgs,
     },
-    /// Plan a query plan for supergraph schema, query and override conditions
-    PlanOne {
-        /// Path to the supergraph schema file.
+    /// Compare two query plan JSON files (produced using the plan-one command)
+    ComparePlans {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
+        schema: PathBuf,
+        /// First query plan result JSON file path.
+        plan1: PathBuf,
+        /// Second query plan result JSON file path.
+        plan2: PathBuf,
+        /// Explain the changes (subgraphs, fetch count, depth, and fields that moved between
+        /// subgraphs) instead of showing the full diff.
+        #[arg(long)]
+        explain: bool,
+    },
+    /// Fail if the query plan for override conditions doesn't match an expected query plan
+    AssertPlan {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
         schema: PathBuf,
         /// Path to the query file, `-` for stdin.
         query: PathBuf,
-        /// Override conditions labels
+        /// Expected query plan result JSON file path (produced using the plan-one command).
+        expected: PathBuf,
+        /// Override conditions labels (`label`, or explicitly `label=true`/`label=false`)
         override_conditions: Vec<String>,
-        /// Override all conditions (equivalent to specifying all labels)
+        /// Name of the operation to plan, if the query file defines several operations.
+        #[arg(long, value_name = "NAME")]
+        operation_name: Option<String>,
+        /// Query planner arguments
+        #[command(flatten)]
+        planner_args: QueryPlannerArgs,
+    },
+    /// Find the smallest set of override conditions that yields a target query plan
+    FindCombo {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
+        schema: PathBuf,
+        /// Path to the query file, `-` for stdin.
+        query: PathBuf,
+        /// Target query plan result JSON file path (produced using the plan-one command).
+        #[arg(required_unless_present = "fingerprint")]
+        target: Option<PathBuf>,
+        /// Target query plan fingerprint (instead of a query plan result file).
+        #[arg(long, conflicts_with = "target")]
+        fingerprint: Option<String>,
+        /// Query planner arguments
+        #[command(flatten)]
+        planner_args: QueryPlannerArgs,
+    },
+    /// List the override condition labels that never change the query plan of a query
+    InsensitiveLabels {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
+        schema: PathBuf,
+        /// Path to the query file, `-` for stdin.
+        query: PathBuf,
+        /// Query planner arguments
+        #[command(flatten)]
+        planner_args: QueryPlannerArgs,
+    },
+    /// Report which override condition labels affect the query plans of a set of operations
+    LabelImpact {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
+        schema: PathBuf,
+        /// Paths to query files, or directories of query files (`.graphql` and `.gql` files).
+        #[arg(required = true)]
+        queries: Vec<PathBuf>,
+        /// Output the report in JSON format.
+        #[arg(long)]
+        json: bool,
+        /// Query planner arguments
+        #[command(flatten)]
+        planner_args: QueryPlannerArgs,
+    },
+    /// List the operations whose query plan changes when an override condition label is toggled
+    ImpactedOperations {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
+        schema: PathBuf,
+        /// The override condition label to toggle.
+        label: String,
+        /// Paths to query files, or directories of query files (`.graphql` and `.gql` files).
+        #[arg(required_unless_present = "manifest")]
+        queries: Vec<PathBuf>,
+        /// Path to a persisted query manifest (JSON) whose operations are analyzed as well.
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+        /// Output the impacted operations in JSON format.
         #[arg(long)]
-        override_all: bool,
+        json: bool,
+        /// Query planner arguments
+        #[command(flatten)]
+        planner_args: QueryPlannerArgs,
+    },
+    /// Compare the override condition labels of two supergraph schemas
+    DiffOverrides {
+        /// Path or HTTP(S) URL of the old supergraph schema file, `-` for stdin.
+        old_schema: PathBuf,
+        /// Path or HTTP(S) URL of the new supergraph schema file, `-` for stdin.
+        new_schema: PathBuf,
+    },
+    /// Compare the query plans of a query under two supergraph schemas
+    DiffSchema {
+        /// Path or HTTP(S) URL of the old supergraph schema file, `-` for stdin.
+        old_schema: PathBuf,
+        /// Path or HTTP(S) URL of the new supergraph schema file, `-` for stdin.
+        new_schema: PathBuf,
+        /// Path to the query file, `-` for stdin.
+        query: PathBuf,
+        /// Override conditions labels (`label`, or explicitly `label=true`/`label=false`)
+        override_conditions: Vec<String>,
+        /// Query planner arguments
+        #[command(flatten)]
+        planner_args: QueryPlannerArgs,
+    },
+    /// Compare the query plans of a query across combinations of boolean query planner options
+    Matrix {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
+        schema: PathBuf,
+        /// Path to the query file, `-` for stdin.
+        query: PathBuf,
+        /// Override conditions labels (`label`, or explicitly `label=true`/`label=false`)
+        override_conditions: Vec<String>,
+        /// Query planner option to vary (can be repeated; all options by default).
+        #[arg(long, value_enum, value_name = "OPTION")]
+        vary: Vec<ConfigAxis>,
         /// Output results in JSON format.
         #[arg(long)]
         json: bool,
@@ -55,62 +338,532 @@ enum Command {
         #[command(flatten)]
         planner_args: QueryPlannerArgs,
     },
-    /// Compare two query plan JSON files (produced using the plan-one command)
-    ComparePlans {
-        /// Path to the supergraph schema file.
+    /// Measure the planning time of a query over repeated runs
+    Bench {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
         schema: PathBuf,
-        /// First query plan result JSON file path.
-        plan1: PathBuf,
-        /// Second query plan result JSON file path.
-        plan2: PathBuf,
+        /// Path to the query file, `-` for stdin.
+        query: PathBuf,
+        /// Override conditions labels (`label`, or explicitly `label=true`/`label=false`)
+        override_conditions: Vec<String>,
+        /// Plan all combinations of override conditions in each iteration.
+        #[arg(long, conflicts_with = "override_conditions")]
+        all_combinations: bool,
+        /// Number of measured iterations.
+        #[arg(long, default_value_t = 10)]
+        iterations: u32,
+        /// Number of iterations to run (and discard) before measuring.
+        #[arg(long, default_value_t = 2)]
+        warmup: u32,
+        /// Query planner arguments
+        #[command(flatten)]
+        planner_args: QueryPlannerArgs,
+    },
+    /// Print the API schema of the supergraph (the schema exposed to clients) as SDL
+    ApiSchema {
+        /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
+        schema: PathBuf,
+        /// Query planner arguments
+        #[command(flatten)]
+        planner_args: QueryPlannerArgs,
     },
+    /// Print the JSON Schema of a query plan result (as produced with `--json`)
+    Schema,
+}
+
+impl Command {
+    /// Whether the command outputs JSON (in which case errors are output as JSON as well)
+    fn json_output(&self) -> bool {
+        match self {
+            Command::ExportOverrides { json, .. }
+            | Command::ListSubgraphs { json, .. }
+            | Command::PlanManifest { json, .. }
+            | Command::LabelImpact { json, .. }
+            | Command::ImpactedOperations { json, .. }
+            | Command::Matrix { json, .. } => *json,
+            Command::Plan(args) => args.json,
+            Command::PlanOne(args) => args.json,
+            _ => false,
+        }
+    }
+}
+
+/// Arguments of the `plan` command
+#[derive(Parser)]
+struct PlanArgs {
+    /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
+    schema: PathBuf,
+    /// Path to the query file, `-` for stdin.
+    query: PathBuf,
+    /// Name of the operation to plan, if the query file defines several operations.
+    #[arg(long, value_name = "NAME")]
+    operation_name: Option<String>,
+    /// Plan every operation of the query file (each one separately).
+    #[arg(long, conflicts_with = "operation_name")]
+    all_operations: bool,
+    /// Output results in JSON format.
+    #[arg(long)]
+    json: bool,
+    /// Text output arguments
+    #[command(flatten)]
+    render_args: RenderArgs,
+    /// Which combinations of override conditions to plan.
+    #[arg(long, value_enum, default_value_t = Enumeration::All)]
+    enumerate: Enumeration,
+    /// Plan a random sample of N distinct combinations (instead of `--enumerate`).
+    #[arg(long, value_name = "N", conflicts_with = "enumerate")]
+    sample: Option<u64>,
+    /// Seed of the random sample (the same seed always picks the same combinations).
+    #[arg(long, default_value_t = 0, requires = "sample")]
+    seed: u64,
+    /// List the combinations of override conditions that would be planned, without planning them.
+    #[arg(long, conflicts_with = "all_operations")]
+    dry_run: bool,
+    /// Fail if any query plan requires more than N sequential fetches (its critical path length).
+    #[arg(long, value_name = "N")]
+    max_sequential: Option<usize>,
+    /// Fail if the combinations yield more than N distinct query plans (by fingerprint).
+    #[arg(long, value_name = "N")]
+    max_distinct_plans: Option<usize>,
+    /// Plan each combination twice, and fail if the two query plans of any combination differ (to
+    /// catch query planner nondeterminism).
+    #[arg(long)]
+    verify_deterministic: bool,
+    /// Sort the results by a metric of their query plans (descending, unless `--sort-asc`).
+    #[arg(long, value_enum)]
+    sort_by: Option<SortKey>,
+    /// Sort the results in ascending order.
+    #[arg(long, requires = "sort_by")]
+    sort_asc: bool,
+    /// Don't print the summary and notes to stderr.
+    #[arg(long, short)]
+    quiet: bool,
+    /// Also write a row for each query plan result to the `query_plans` table of this SQLite
+    /// database (requires the `sqlite` feature).
+    #[arg(long, value_name = "PATH")]
+    sqlite: Option<PathBuf>,
+    /// Write Prometheus text-format metrics of the run to this file.
+    #[arg(long, value_name = "PATH")]
+    metrics: Option<PathBuf>,
+    /// Keep planning the other combinations when a combination fails to plan, and write the
+    /// failed combinations (with their errors) to this JSON file.
+    #[arg(long, value_name = "PATH")]
+    report_failures: Option<PathBuf>,
+    /// Baseline comparison arguments
+    #[command(flatten)]
+    baseline_args: BaselineArgs,
+    /// Query plan statistics arguments
+    #[command(flatten)]
+    cost_args: CostArgs,
+    /// Query warning arguments
+    #[command(flatten)]
+    warning_args: WarningArgs,
+    /// Query planner arguments
+    #[command(flatten)]
+    planner_args: QueryPlannerArgs,
+}
+
+impl PlanArgs {
+    /// Which combinations of override conditions to plan (`--enumerate` or `--sample`)
+    fn enumeration_mode(&self) -> EnumerationMode {
+        match self.sample {
+            Some(count) => EnumerationMode::Sample {
+                count,
+                seed: self.seed,
+            },
+            None => self.enumerate.into(),
+        }
+    }
+}
+
+/// Arguments of the `plan-one` command
+#[derive(Parser)]
+struct PlanOneArgs {
+    /// Path or HTTP(S) URL of the supergraph schema file, `-` for stdin.
+    schema: PathBuf,
+    /// Path to the query file, `-` for stdin.
+    query: PathBuf,
+    /// Override conditions labels (`label`, or explicitly `label=true`/`label=false`)
+    override_conditions: Vec<String>,
+    /// Path to a file listing override condition labels (one label per line, a JSON array, or
+    /// a JSON object mapping labels to `true`/`false`). Lines starting with `#` are comments.
+    #[arg(long, conflicts_with = "override_all")]
+    conditions_file: Option<PathBuf>,
+    /// Override all conditions (equivalent to specifying all labels)
+    #[arg(long)]
+    override_all: bool,
+    /// Output results in JSON format.
+    #[arg(long)]
+    json: bool,
+    /// Plan with `experimental_type_conditioned_fetching` off and on, and show whether (and how)
+    /// the query plans differ.
+    #[arg(long, conflicts_with = "json")]
+    compare_type_conditioned: bool,
+    /// Text output arguments
+    #[command(flatten)]
+    render_args: RenderArgs,
+    /// Query warning arguments
+    #[command(flatten)]
+    warning_args: WarningArgs,
+    /// Query planner arguments
+    #[command(flatten)]
+    planner_args: QueryPlannerArgs,
+}
+
+/// Formats of the log output
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// A JSON object per event (for log ingestion)
+    Json,
+}
+
+/// When to color the error output
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ColorChoice {
+    /// Color if stderr is a terminal, unless the `NO_COLOR` environment variable is set
+    Auto,
+    /// Always color (even if stderr isn't a terminal)
+    Always,
+    /// Never color
+    Never,
+}
+
+impl ColorChoice {
+    fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => {
+                std::io::stderr().is_terminal()
+                    && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Levels of the log output
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl From<LogLevel> for tracing::Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Trace => tracing::Level::TRACE,
+            LogLevel::Debug => tracing::Level::DEBUG,
+            LogLevel::Info => tracing::Level::INFO,
+            LogLevel::Warn => tracing::Level::WARN,
+            LogLevel::Error => tracing::Level::ERROR,
+        }
+    }
+}
+
+/// Text output formats for query plans
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum OutputFormat {
+    /// The query plan display, as printed by Apollo Router
+    Text,
+    /// An indented tree of plan nodes using box-drawing connectors
+    Tree,
+    /// The query plan JSON, in the shape Apollo Router logs it
+    RouterJson,
+    /// A GraphViz DOT digraph, with fetch nodes colored by subgraph
+    Dot,
+    /// A Mermaid flowchart
+    Mermaid,
+    /// A single line in a bracketed notation (like `Sequence[Fetch(a), Fetch(b)]`)
+    Compact,
+    /// The plan nodes as JSON, with stable IDs assigned in pre-order
+    NodeJson,
+}
+
+/// Metrics to sort query plan results by
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum SortKey {
+    /// The cost of the query plan (see `--stats`)
+    Cost,
+    /// The number of fetches
+    Fetches,
+    /// The number of fetches on the critical path
+    Depth,
+    /// The enumeration index of the combination
+    Index,
+}
+
+/// Boolean query planner options varied by the `matrix` command
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ConfigAxis {
+    /// `type_conditioned_fetching`
+    TypeConditionedFetching,
+    /// `generate_query_fragments`
+    GenerateQueryFragments,
+    /// `incremental_delivery.enable_defer`
+    DeferSupport,
+}
+
+impl ConfigAxis {
+    const ALL: [ConfigAxis; 3] = [
+        ConfigAxis::TypeConditionedFetching,
+        ConfigAxis::GenerateQueryFragments,
+        ConfigAxis::DeferSupport,
+    ];
+
+    /// The name of the option in `QueryPlannerConfig`
+    fn name(self) -> &'static str {
+        match self {
+            ConfigAxis::TypeConditionedFetching => "type_conditioned_fetching",
+            ConfigAxis::GenerateQueryFragments => "generate_query_fragments",
+            ConfigAxis::DeferSupport => "enable_defer",
+        }
+    }
+
+    fn set(self, config: &mut QueryPlannerConfig, enabled: bool) {
+        match self {
+            ConfigAxis::TypeConditionedFetching => config.type_conditioned_fetching = enabled,
+            ConfigAxis::GenerateQueryFragments => config.generate_query_fragments = enabled,
+            ConfigAxis::DeferSupport => config.incremental_delivery.enable_defer = enabled,
+        }
+    }
+}
+
+/// Enumeration modes of override condition combinations
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Enumeration {
+    /// All possible combinations (2^n combinations for n labels)
+    All,
+    /// No overrides, plus each label enabled alone (n+1 combinations)
+    OneHot,
+    /// No overrides, each label enabled alone, plus each pair of labels (O(n^2) combinations)
+    Pairwise,
+}
+
+impl From<Enumeration> for EnumerationMode {
+    fn from(enumeration: Enumeration) -> Self {
+        match enumeration {
+            Enumeration::All => EnumerationMode::All,
+            Enumeration::OneHot => EnumerationMode::OneHot,
+            Enumeration::Pairwise => EnumerationMode::Pairwise,
+        }
+    }
+}
+
+/// Arguments comparing each combination's query plan against a baseline combination
+#[derive(Parser)]
+struct BaselineArgs {
+    /// Only output combinations whose query plan differs from the baseline.
+    #[arg(long)]
+    pub(crate) only_changed: bool,
+
+    /// Override condition labels of the baseline combination (comma-separated).
+    /// No overrides by default.
+    #[arg(long, value_delimiter = ',')]
+    pub(crate) baseline: Vec<String>,
+
+    /// Print the difference of each combination's query plan from the baseline.
+    #[arg(long, conflicts_with = "json")]
+    pub(crate) show_diff: bool,
+}
+
+impl BaselineArgs {
+    fn is_enabled(&self) -> bool {
+        self.only_changed || self.show_diff
+    }
+
+    /// Rejects a `--baseline` that nothing compares against (instead of ignoring it).
+    fn check(&self) -> Result<(), AnyError> {
+        if !self.baseline.is_empty() && !self.is_enabled() {
+            return Err(anyhow!(
+                "`--baseline` requires `--only-changed` or `--show-diff`"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Arguments of the text rendering of query plans
+#[derive(Parser)]
+struct RenderArgs {
+    /// Text output format.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, conflicts_with = "json")]
+    pub(crate) format: OutputFormat,
+
+    /// Link fetch nodes of the Mermaid output to this URL template (`{subgraph}` is replaced by the
+    /// subgraph name).
+    #[arg(long, value_name = "TEMPLATE")]
+    pub(crate) node_link: Option<String>,
+
+    /// Indentation width of each level of the `tree` format and the JSON formats (3 and 2 columns
+    /// by default, respectively).
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u8).range(2..=16))]
+    pub(crate) indent: Option<u8>,
+
+    /// Prefix each fetch of the `text` and `tree` formats with the subgraph it is sent to (like
+    /// `[accounts]`).
+    #[arg(long)]
+    pub(crate) annotate_subgraphs: bool,
+}
+
+/// Arguments handling the warnings of the query (like the selection of deprecated fields)
+#[derive(Parser)]
+struct WarningArgs {
+    /// Don't print the warnings of the query to stderr (they are still included in the JSON
+    /// output).
+    #[arg(long)]
+    pub(crate) allow_warnings: bool,
+
+    /// Fail if the query has any warnings.
+    #[arg(long, conflicts_with = "allow_warnings")]
+    pub(crate) strict: bool,
+}
+
+impl WarningArgs {
+    /// Prints the warnings to stderr, or fails on any of them in strict mode.
+    fn check(&self, warnings: &[String]) -> Result<(), AnyError> {
+        if self.strict && !warnings.is_empty() {
+            return Err(anyhow!(
+                "The query has {} warnings (`--strict`):\n{}",
+                warnings.len(),
+                warnings.join("\n")
+            ));
+        }
+        if !self.allow_warnings {
+            for warning in warnings {
+                eprintln!("Warning: {warning}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Arguments of the query plan statistics and the cost model
+#[derive(Parser)]
+struct CostArgs {
+    /// Print the statistics and the cost of each query plan.
+    #[arg(long)]
+    pub(crate) stats: bool,
+
+    /// Cost of each fetch.
+    #[arg(long, default_value_t = CostWeights::default().fetch)]
+    pub(crate) cost_weight_fetch: f64,
+
+    /// Additional cost of each entity fetch.
+    #[arg(long, default_value_t = CostWeights::default().entity_fetch)]
+    pub(crate) cost_weight_entity_fetch: f64,
+
+    /// Cost of each fetch on the critical path (fetches that run one after another).
+    #[arg(long, default_value_t = CostWeights::default().depth)]
+    pub(crate) cost_weight_depth: f64,
+
+    /// Cost of each selection required by entity fetches.
+    #[arg(long, default_value_t = CostWeights::default().requires)]
+    pub(crate) cost_weight_requires: f64,
+}
+
+impl From<&CostArgs> for CostWeights {
+    fn from(args: &CostArgs) -> Self {
+        CostWeights {
+            fetch: args.cost_weight_fetch,
+            entity_fetch: args.cost_weight_entity_fetch,
+            depth: args.cost_weight_depth,
+            requires: args.cost_weight_requires,
+        }
+    }
 }
 
 /// Query-planner-related arguments
 /// * Reflecting the Router configuration options.
-#[derive(Parser)]
+/// * Options that are not given default to their `QP_*` environment variables, then to the
+///   `--config` file's, then to the built-in defaults.
+#[derive(Clone, Parser)]
 struct QueryPlannerArgs {
     /// Disable optimization of subgraph fetch queries using fragments.
-    #[arg(long)]
-    pub(crate) disable_generate_query_fragments: bool,
+    #[arg(
+        long,
+        env = "QP_DISABLE_GENERATE_QUERY_FRAGMENTS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new()
+    )]
+    pub(crate) disable_generate_query_fragments: Option<bool>,
 
     /// Disable defer support.
-    #[arg(long)]
-    pub(crate) disable_defer_support: bool,
+    #[arg(
+        long,
+        env = "QP_DISABLE_DEFER_SUPPORT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new()
+    )]
+    pub(crate) disable_defer_support: Option<bool>,
 
     /// Enable type conditioned fetching.
-    #[arg(long, default_value_t = false)]
-    pub(crate) experimental_type_conditioned_fetching: bool,
+    #[arg(
+        long,
+        env = "QP_EXPERIMENTAL_TYPE_CONDITIONED_FETCHING",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new()
+    )]
+    pub(crate) experimental_type_conditioned_fetching: Option<bool>,
 
-    /// Sets a limit to the number of generated query plans.
-    #[arg(long, default_value_t = 10_000)]
-    pub(crate) experimental_plans_limit: u32,
+    /// Sets a limit to the number of generated query plans (10000 by default).
+    #[arg(long, env = "QP_EXPERIMENTAL_PLANS_LIMIT")]
+    pub(crate) experimental_plans_limit: Option<u32>,
 
     /// Specify a per-path limit to the number of options considered.
     /// No limit is applied by default. Also, if set to `0`, it is treated as no limit.
-    #[arg(long, default_value_t = 0)]
-    pub(crate) experimental_paths_limit: u32,
+    #[arg(long, env = "QP_EXPERIMENTAL_PATHS_LIMIT")]
+    pub(crate) experimental_paths_limit: Option<u32>,
 }
 
-impl From<QueryPlannerArgs> for QueryPlannerConfig {
-    fn from(args: QueryPlannerArgs) -> Self {
-        let max_evaluated_plans = NonZeroU32::new(args.experimental_plans_limit)
+impl QueryPlannerArgs {
+    /// Resolves the query planner config: the options given on the command line (or by their
+    /// `QP_*` environment variables) take precedence over the ones of the config `file`.
+    fn to_config(&self, file: &PlannerConfigFile) -> QueryPlannerConfig {
+        let flag =
+            |arg: Option<bool>, file_value: Option<bool>| arg.or(file_value).unwrap_or(false);
+        let experimental_plans_limit = self
+            .experimental_plans_limit
+            .or(file.experimental_plans_limit)
+            .unwrap_or(10_000);
+        let experimental_paths_limit = self
+            .experimental_paths_limit
+            .or(file.experimental_paths_limit)
+            .unwrap_or(0);
+
+        let max_evaluated_plans = NonZeroU32::new(experimental_plans_limit)
             // If experimental_plans_limit is zero; use our default.
             .unwrap_or(NonZeroU32::new(10_000).unwrap());
-        let paths_limit = if args.experimental_paths_limit == 0 {
+        let paths_limit = if experimental_paths_limit == 0 {
             None
         } else {
-            Some(args.experimental_paths_limit)
+            Some(experimental_paths_limit)
         };
 
         QueryPlannerConfig {
             // `subgraph_graphql_validation` is false in Router, but we may consider enabling it.
             subgraph_graphql_validation: false,
-            generate_query_fragments: !args.disable_generate_query_fragments,
+            generate_query_fragments: !flag(
+                self.disable_generate_query_fragments,
+                file.disable_generate_query_fragments,
+            ),
             incremental_delivery: QueryPlanIncrementalDeliveryConfig {
-                enable_defer: !args.disable_defer_support,
+                enable_defer: !flag(self.disable_defer_support, file.disable_defer_support),
             },
-            type_conditioned_fetching: args.experimental_type_conditioned_fetching,
+            type_conditioned_fetching: flag(
+                self.experimental_type_conditioned_fetching,
+                file.experimental_type_conditioned_fetching,
+            ),
             debug: QueryPlannerDebugConfig {
                 max_evaluated_plans,
                 paths_limit,
@@ -120,121 +873,1437 @@ impl From<QueryPlannerArgs> for QueryPlannerConfig {
 }
 
 fn main() {
-    init_tracing();
-    let cmd = Command::parse();
-    let result = match cmd {
-        Command::ListOverrides { schema } => cmd_overrides(&schema),
-        Command::PlanOne {
+    let cli = Cli::parse();
+    init_tracing(cli.log_format, cli.log_level);
+    let json_output = cli.command.json_output();
+    let color = cli.color.enabled();
+    input::set_fetch_timeout(Duration::from_secs(cli.fetch_timeout));
+    if let Some(jobs) = cli.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs.get())
+            .build_global()
+            .expect("the global thread pool is only configured once");
+    }
+    if let Err(e) = output::init_output(cli.output.as_deref(), cli.redact_urls) {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+    // Note: The config file is loaded by the command, so that its errors are reported like any
+    //       other (as JSON, with `--json`).
+    let result = run_command(cli.command, cli.config.as_deref(), cli.redact_urls);
+    // The output written before an error is flushed as well.
+    let flushed = output::flush_output();
+    let result = result.and(flushed);
+    if let Err(e) = result {
+        if e.is::<ReportedError>() {
+            // The error is already part of the JSON output, which must stay a single document.
+            eprintln!("Error: {e}");
+        } else if json_output {
+            // Keep the output valid JSON for the consumers of the JSON output.
+            let report = ErrorReport {
+                error: ErrorDetails::new(&e),
+            };
+            let written = output::write_json(&report).and_then(|()| output::flush_output());
+            if let Err(write_error) = written {
+                eprintln!("Error: {e}\nError: {write_error}");
+            }
+        } else if let Some(invalid) = e.downcast_ref::<InvalidQuery>() {
+            eprintln!("Error: invalid query\n{}", invalid.render(color));
+        } else {
+            eprintln!("Error: {e}");
+        }
+        std::process::exit(1);
+    }
+}
+
+/// Runs the command, with the query planner options of the `--config` file as defaults.
+fn run_command(
+    command: Command,
+    config_path: Option<&Path>,
+    redact_urls: bool,
+) -> Result<(), AnyError> {
+    let config_file = match config_path {
+        Some(path) => config_file::load_config_file(path)?,
+        None => PlannerConfigFile::default(),
+    };
+    match command {
+        Command::ListOverrides { schema, describe } => cmd_overrides(&schema, describe),
+        Command::ExportOverrides { schema, json } => cmd_export_overrides(&schema, json),
+        Command::CountCombinations { schema, quiet } => cmd_count_combinations(&schema, quiet),
+        Command::ListSubgraphs { schema, urls, json } => {
+            cmd_list_subgraphs(&schema, urls, json, redact_urls)
+        }
+        Command::SubgraphSdl { schema, name } => cmd_subgraph_sdl(&schema, &name),
+        Command::ListOperations { schema, query } => cmd_list_operations(&schema, &query),
+        Command::PlanOne(args) => {
+            let config = args.planner_args.to_config(&config_file);
+            cmd_build_one_plan(args, config)
+        }
+        Command::Plan(args) => {
+            let config = args.planner_args.to_config(&config_file);
+            cmd_build_all_plans(args, config)
+        }
+        Command::PlanManifest {
             schema,
-            query,
+            manifest,
+            json,
+            enumerate,
+            sqlite,
+            metrics,
             planner_args,
-            override_conditions,
-            override_all,
+        } => cmd_plan_manifest(
+            &schema,
+            &manifest,
             json,
-        } => cmd_build_one_plan(
+            enumerate,
+            sqlite.as_deref(),
+            metrics.as_deref(),
+            planner_args.to_config(&config_file),
+        ),
+        Command::ComparePlans {
+            schema,
+            plan1,
+            plan2,
+            explain,
+        } => cmd_compare_plans(&schema, &plan1, &plan2, explain),
+        Command::AssertPlan {
+            schema,
+            query,
+            expected,
+            override_conditions,
+            operation_name,
+            planner_args,
+        } => cmd_assert_plan(
             &schema,
             &query,
+            &expected,
+            &override_conditions,
+            operation_name.as_deref(),
+            planner_args.to_config(&config_file),
+        ),
+        Command::FindCombo {
+            schema,
+            query,
+            target,
+            fingerprint,
             planner_args,
-            override_conditions,
-            override_all,
-            json,
+        } => cmd_find_combo(
+            &schema,
+            &query,
+            target,
+            fingerprint,
+            planner_args.to_config(&config_file),
         ),
-        Command::Plan {
+        Command::InsensitiveLabels {
             schema,
             query,
             planner_args,
+        } => cmd_insensitive_labels(&schema, &query, planner_args.to_config(&config_file)),
+        Command::LabelImpact {
+            schema,
+            queries,
             json,
-        } => cmd_build_all_plans(&schema, &query, planner_args, json),
-        Command::ComparePlans {
+            planner_args,
+        } => cmd_label_impact(
+            &schema,
+            &queries,
+            json,
+            planner_args.to_config(&config_file),
+        ),
+        Command::ImpactedOperations {
             schema,
-            plan1,
-            plan2,
-        } => cmd_compare_plans(&schema, &plan1, &plan2),
-    };
-    if let Err(e) = result {
-        eprintln!("Error: {e}");
-        std::process::exit(1);
-    }
-}
+            label,
+            queries,
+            manifest,
+            json,
+            planner_args,
+        } => cmd_impacted_operations(
+            &schema,
+            &label,
+            &queries,
+            manifest.as_deref(),
+            json,
+            planner_args.to_config(&config_file),
+        ),
+        Command::DiffOverrides {
+            old_schema,
+            new_schema,
+        } => cmd_diff_overrides(&old_schema, &new_schema),
+        Command::DiffSchema {
+            old_schema,
+            new_schema,
+            query,
+            override_conditions,
+            planner_args,
+        } => cmd_diff_schema(
+            &old_schema,
+            &new_schema,
+            &query,
+            override_conditions,
+            planner_args.to_config(&config_file),
+        ),
+        Command::Matrix {
+            schema,
+            query,
+            override_conditions,
+            vary,
+            json,
+            planner_args,
+        } => cmd_matrix(
+            &schema,
+            &query,
+            override_conditions,
+            vary,
+            json,
+            planner_args.to_config(&config_file),
+        ),
+        Command::Bench {
+            schema,
+            query,
+            override_conditions,
+            all_combinations,
+            iterations,
+            warmup,
+            planner_args,
+        } => cmd_bench(
+            &schema,
+            &query,
+            override_conditions,
+            all_combinations,
+            iterations,
+            warmup,
+            planner_args.to_config(&config_file),
+        ),
+        Command::ApiSchema {
+            schema,
+            planner_args,
+        } => cmd_api_schema(&schema, planner_args.to_config(&config_file)),
+        Command::Schema => cmd_print_output_schema(),
+    }
+}
+
+/// JSON error output of the commands run with `--json`
+#[derive(serde::Serialize)]
+struct ErrorReport {
+    error: ErrorDetails,
+}
+
+#[derive(serde::Serialize)]
+struct ErrorDetails {
+    message: String,
+    /// The enabled override conditions of the combination that failed to plan, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    combination: Option<Vec<String>>,
+}
+
+impl ErrorDetails {
+    fn new(error: &AnyError) -> Self {
+        ErrorDetails {
+            message: error.to_string(),
+            combination: error
+                .downcast_ref::<CombinationError>()
+                .map(|e| e.override_conditions.clone()),
+        }
+    }
+}
+
+/// An error already reported in the `error` field of a JSON report, so that `main` only sets the
+/// exit status (instead of writing a second JSON document)
+#[derive(Debug)]
+struct ReportedError(String);
+
+impl fmt::Display for ReportedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ReportedError {}
+
+/// Marks an error of a command as already part of its JSON report (in `--json` mode).
+fn reported_in_json(json_output: bool, error: AnyError) -> AnyError {
+    if json_output {
+        ReportedError(error.to_string()).into()
+    } else {
+        error
+    }
+}
+
+/// An error of planning a specific combination of override conditions
+#[derive(Debug)]
+struct CombinationError {
+    override_conditions: Vec<String>,
+    source: AnyError,
+}
+
+impl fmt::Display for CombinationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl std::error::Error for CombinationError {}
 
 /// Set up the tracing subscriber
-fn init_tracing() {
-    let fmt_layer = tracing_subscriber::fmt::layer()
-        .without_time()
-        .with_target(false);
-    let filter_layer = tracing_subscriber::EnvFilter::from_default_env();
+fn init_tracing(log_format: LogFormat, log_level: Option<LogLevel>) {
+    // Only one of the layers is set, depending on the format.
+    let (text_layer, json_layer) = match log_format {
+        LogFormat::Text => {
+            let text_layer = tracing_subscriber::fmt::layer()
+                .without_time()
+                .with_target(false);
+            (Some(text_layer), None)
+        }
+        LogFormat::Json => (None, Some(tracing_subscriber::fmt::layer().json())),
+    };
+    let filter_layer = match log_level {
+        Some(level) if std::env::var_os(EnvFilter::DEFAULT_ENV).is_none() => {
+            let level = LevelFilter::from_level(level.into());
+            EnvFilter::default().add_directive(level.into())
+        }
+        _ => EnvFilter::from_default_env(),
+    };
     tracing_subscriber::registry()
-        .with(fmt_layer)
+        .with(text_layer)
+        .with(json_layer)
         .with(filter_layer)
         .init();
 }
 
-fn cmd_overrides(schema_path: &Path) -> Result<(), AnyError> {
-    let override_labels = get_override_labels(&read_input(schema_path))?;
+fn cmd_overrides(schema_path: &Path, describe: bool) -> Result<(), AnyError> {
+    let schema_str = read_input(schema_path)?;
+    output::redact_routing_urls(&schema_str);
+    let override_labels = get_override_labels(&schema_str)?;
+    let descriptions = if describe {
+        describe_override_labels(&schema_str)?
+    } else {
+        Default::default()
+    };
     for label in override_labels {
-        println!("{label}");
+        if !describe {
+            outputln!("{label}")?;
+            continue;
+        }
+        match override_label_percentage(&label) {
+            Some(percentage) => outputln!("{label} (progressive override: {percentage}%)")?,
+            None => outputln!("{label} (feature flag)")?,
+        }
+        for target in descriptions.get(label.as_ref()).into_iter().flatten() {
+            outputln!(
+                "  {}.{} (from: {})",
+                target.type_name,
+                target.field_name,
+                target.from
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn cmd_count_combinations(schema_path: &Path, quiet: bool) -> Result<(), AnyError> {
+    let schema_str = read_input(schema_path)?;
+    output::redact_routing_urls(&schema_str);
+    let label_count = get_override_labels(&schema_str)?.len();
+    let combination_count = EnumerationMode::All.combination_count(label_count);
+    if quiet {
+        outputln!("{combination_count}")
+    } else {
+        outputln!("{label_count} override condition labels, {combination_count} combinations")
+    }
+}
+
+/// JSON output of the `export-overrides` command (for each label)
+#[derive(serde::Serialize)]
+struct OverrideLabelExport {
+    label: String,
+    targets: Vec<OverrideTarget>,
+}
+
+fn cmd_export_overrides(schema_path: &Path, json_output: bool) -> Result<(), AnyError> {
+    let schema_str = read_input(schema_path)?;
+    output::redact_routing_urls(&schema_str);
+    let mut descriptions = describe_override_labels(&schema_str)?;
+    let exports: Vec<_> = get_override_labels(&schema_str)?
+        .iter()
+        .map(|label| OverrideLabelExport {
+            label: label.to_string(),
+            targets: descriptions.swap_remove(label.as_ref()).unwrap_or_default(),
+        })
+        .collect();
+    if json_output {
+        output::write_json(&exports)?;
+        return Ok(());
+    }
+    for export in exports {
+        for target in export.targets {
+            outputln!(
+                "{}\t{}.{}\t{}",
+                export.label,
+                target.type_name,
+                target.field_name,
+                target.from
+            )?;
+        }
     }
     Ok(())
 }
 
-fn cmd_build_all_plans(
+fn cmd_list_subgraphs(
     schema_path: &Path,
-    query_path: &Path,
-    planner_args: QueryPlannerArgs,
+    urls: bool,
     json_output: bool,
+    redact_urls: bool,
 ) -> Result<(), AnyError> {
-    let results = build_all_plans(
-        &read_input(schema_path),
-        &read_input(query_path),
-        query_path,
-        planner_args.into(),
-        !json_output,
-    )?;
+    let schema_str = read_input(schema_path)?;
+    let mut subgraphs = list_subgraphs(&schema_str)?;
+    if redact_urls {
+        // The routing URLs are the output of this command, so they are redacted right away.
+        for subgraph in &mut subgraphs {
+            if subgraph.url.is_some() {
+                subgraph.url = Some(REDACTED.to_string());
+            }
+        }
+    }
     if json_output {
-        println!("{}", serde_json::to_string_pretty(&results).unwrap());
+        if urls {
+            // Subgraphs are listed in alphabetical order, which the `BTreeMap` keeps.
+            let urls: BTreeMap<_, _> = subgraphs
+                .into_iter()
+                .map(|subgraph| (subgraph.name, subgraph.url))
+                .collect();
+            output::write_json(&urls)?;
+        } else {
+            let names: Vec<_> = subgraphs
+                .into_iter()
+                .map(|subgraph| subgraph.name)
+                .collect();
+            output::write_json(&names)?;
+        }
+        return Ok(());
+    }
+    for subgraph in subgraphs {
+        if urls {
+            let url = subgraph.url.as_deref().unwrap_or("(no routing URL)");
+            outputln!("{} -> {url}", subgraph.name)?;
+        } else {
+            outputln!("{}", subgraph.name)?;
+        }
     }
     Ok(())
 }
 
-fn cmd_build_one_plan(
-    schema_path: &Path,
-    query_path: &Path,
-    planner_args: QueryPlannerArgs,
+fn cmd_subgraph_sdl(schema_path: &Path, subgraph_name: &str) -> Result<(), AnyError> {
+    let schema_str = read_input(schema_path)?;
+    output::redact_routing_urls(&schema_str);
+    output!("{}", subgraph_sdl(&schema_str, subgraph_name)?)?;
+    Ok(())
+}
+
+fn cmd_list_operations(schema_path: &Path, query_path: &Path) -> Result<(), AnyError> {
+    let [schema_str, query_str] = read_inputs([schema_path, query_path])?;
+    output::redact_routing_urls(&schema_str);
+    let query_path = source_name(query_path);
+    let operations =
+        list_operations(&schema_str, &query_str, query_path).map_err(with_query_diagnostics)?;
+    for operation in operations {
+        let name = operation.name.as_deref().unwrap_or("(anonymous)");
+        outputln!("{} {name}", operation.operation_type)?;
+    }
+    Ok(())
+}
+
+/// JSON output of the `plan` command
+#[derive(serde::Serialize)]
+struct PlanReport {
+    /// The override conditions of the baseline combination, if compared against one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    baseline: Option<Vec<String>>,
+    /// A note on the combinations left out of `results`, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+    /// Statistics over all evaluated combinations (including filtered-out ones)
+    summary: PlanSummary,
+    results: Vec<PlanEntry>,
+    /// Why the run failed after planning (like an exceeded `--max-sequential`), if it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ErrorDetails>,
+}
+
+/// A query plan result in the JSON output of the `plan` command
+#[derive(serde::Serialize)]
+struct PlanEntry {
+    #[serde(flatten)]
+    result: QueryPlanResult,
+    /// The statistics and the cost of the query plan (with `--stats`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<PlanStats>,
+}
+
+/// JSON output of the `plan` command with `--all-operations`
+#[derive(serde::Serialize)]
+struct OperationsReport {
+    operations: Vec<OperationReport>,
+    /// Why the run failed after planning (like operations that failed to plan), if it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ErrorDetails>,
+}
+
+/// The results of an operation in the JSON output of the `plan` command with `--all-operations`
+#[derive(serde::Serialize)]
+struct OperationReport {
+    /// The operation name (null for an anonymous operation)
+    operation_name: Option<String>,
+    /// Why the operation could not be planned, if it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(flatten)]
+    report: Option<PlanReport>,
+}
+
+/// The query plans of an operation, as computed by the `plan` command
+struct PlanRun {
+    baseline: Option<QueryPlanResult>,
+    /// A note on the combinations left out of `results`, if any
+    note: Option<String>,
+    summary: PlanSummary,
+    /// The results to output, with the enumeration index of their combination
+    results: Vec<(usize, QueryPlanResult)>,
+    /// The combinations exceeding `--max-sequential`, described
+    sequential_violations: Vec<String>,
+    /// The number of distinct query plans, if it exceeds `--max-distinct-plans`, described
+    distinct_plans_violation: Option<String>,
+    /// The combinations that failed to plan (with `--report-failures`), with their enumeration
+    /// index
+    failures: Vec<(usize, CombinationFailure)>,
+    /// The combinations whose query plan changed when planned again (with
+    /// `--verify-deterministic`), described
+    nondeterministic: Vec<String>,
+}
+
+/// JSON report of the combinations that failed to plan (`plan --report-failures`)
+#[derive(serde::Serialize)]
+struct FailureReport {
+    failures: Vec<FailureEntry>,
+}
+
+/// A combination that failed to plan, in the JSON report of `plan --report-failures`
+#[derive(serde::Serialize)]
+struct FailureEntry {
+    /// The operation (with `--all-operations`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operation: Option<String>,
+    /// The enumeration index of the combination (as in `Override Combination #i`)
+    index: usize,
+    combination_name: String,
     override_conditions: Vec<String>,
-    override_all: bool,
-    json_output: bool,
-) -> Result<(), AnyError> {
-    let override_conditions = if override_all && override_conditions.is_empty() {
+    error: String,
+}
+
+impl FailureEntry {
+    fn new(operation: Option<&str>, (index, failure): (usize, CombinationFailure)) -> Self {
+        FailureEntry {
+            operation: operation.map(str::to_string),
+            index,
+            combination_name: failure.combination_name,
+            override_conditions: failure.override_conditions,
+            error: failure.error.to_string(),
+        }
+    }
+}
+
+/// Writes the failure report (with `--report-failures`), and fails if any combination failed.
+fn write_failure_report(args: &PlanArgs, failures: Vec<FailureEntry>) -> Result<(), AnyError> {
+    let Some(path) = &args.report_failures else {
+        return Ok(());
+    };
+    let count = failures.len();
+    let report = serde_json::to_string_pretty(&FailureReport { failures })?;
+    fs::write(path, report + "\n")
+        .map_err(|e| anyhow!("Failed to write {}: {e}", path.display()))?;
+    if count > 0 {
+        return Err(anyhow!(
+            "{count} combinations failed to plan (see {})",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+fn cmd_build_all_plans(args: PlanArgs, config: QueryPlannerConfig) -> Result<(), AnyError> {
+    args.baseline_args.check()?;
+    let query_path = args.query.as_path();
+    let [schema_str, query_str] = read_inputs([args.schema.as_path(), query_path])?;
+    output::redact_routing_urls(&schema_str);
+    let query_path = source_name(query_path);
+    if args.dry_run {
+        return print_dry_run(&args, &schema_str);
+    }
+    let mut sqlite = match &args.sqlite {
+        Some(path) => Some(SqliteWriter::create(path, (&args.cost_args).into())?),
+        None => None,
+    };
+
+    if !args.all_operations {
+        let run = plan_operation(
+            &args,
+            &schema_str,
+            &query_str,
+            config,
+            args.operation_name.as_deref(),
+        );
+        if let Some(metrics_path) = &args.metrics {
+            let mut metrics = RunMetrics::default();
+            match &run {
+                Ok(run) => metrics.add(&run.summary),
+                Err(_) => metrics.add_failure(),
+            }
+            metrics.write(metrics_path)?;
+        }
+        let mut run = run?;
+        if let Some(sqlite) = &mut sqlite {
+            let operation = operation_display_name(query_path, args.operation_name.as_deref());
+            sqlite.insert(&operation, run.results.iter().map(|(_, result)| result))?;
+        }
+        let sequential_violations = std::mem::take(&mut run.sequential_violations);
+        let distinct_plans_violations: Vec<_> =
+            run.distinct_plans_violation.take().into_iter().collect();
+        let nondeterministic = std::mem::take(&mut run.nondeterministic);
+        let failures: Vec<_> = std::mem::take(&mut run.failures)
+            .into_iter()
+            .map(|failure| FailureEntry::new(None, failure))
+            .collect();
+        let check = || {
+            write_failure_report(&args, failures)?;
+            check_max_sequential(&args, &sequential_violations)?;
+            check_max_distinct_plans(&args, &distinct_plans_violations)?;
+            check_deterministic(&nondeterministic)
+        };
+        if args.json {
+            // The checks run first, so that their error is part of the report.
+            let checked = check();
+            let mut report = plan_report(&args, run);
+            report.error = checked.as_ref().err().map(ErrorDetails::new);
+            output::write_json(&report)?;
+            return checked.map_err(|e| reported_in_json(true, e));
+        }
+        print_plan_run(&args, &schema_str, &run)?;
+        return check();
+    }
+
+    // Plan each operation separately, so that a failing operation doesn't abort the others.
+    let operations = operation_names(&query_str, query_path)?;
+    let mut reports = Vec::new();
+    let mut sequential_violations = Vec::new();
+    let mut distinct_plans_violations = Vec::new();
+    let mut combination_failures = Vec::new();
+    let mut nondeterministic = Vec::new();
+    let mut failures = 0;
+    let mut metrics = RunMetrics::default();
+    // The operations are planned concurrently (see `--jobs`), and output in document order.
+    let runs: Vec<_> = operations
+        .par_iter()
+        .map(|operation_name| {
+            plan_operation(
+                &args,
+                &schema_str,
+                &query_str,
+                config.clone(),
+                operation_name.as_deref(),
+            )
+        })
+        .collect();
+    for (operation_name, run) in operations.iter().zip(runs) {
+        let display_name = operation_name.as_deref().unwrap_or("(anonymous)");
+        let (report, error) = match run {
+            Ok(mut run) => {
+                metrics.add(&run.summary);
+                if let Some(sqlite) = &mut sqlite {
+                    let operation = operation_display_name(query_path, operation_name.as_deref());
+                    sqlite.insert(&operation, run.results.iter().map(|(_, result)| result))?;
+                }
+                sequential_violations.extend(
+                    run.sequential_violations
+                        .drain(..)
+                        .map(|violation| format!("Operation {display_name}: {violation}")),
+                );
+                distinct_plans_violations.extend(
+                    run.distinct_plans_violation
+                        .take()
+                        .map(|violation| format!("Operation {display_name}: {violation}")),
+                );
+                nondeterministic.extend(
+                    run.nondeterministic
+                        .drain(..)
+                        .map(|description| format!("Operation {display_name}: {description}")),
+                );
+                combination_failures.extend(
+                    run.failures
+                        .drain(..)
+                        .map(|failure| FailureEntry::new(Some(display_name), failure)),
+                );
+                if args.json {
+                    (Some(plan_report(&args, run)), None)
+                } else {
+                    let separator = "=".repeat(71);
+                    outputln!("{separator}\nOperation: {display_name}\n{separator}")?;
+                    print_plan_run(&args, &schema_str, &run)?;
+                    (None, None)
+                }
+            }
+            Err(e) => {
+                failures += 1;
+                metrics.add_failure();
+                if !args.json {
+                    eprintln!("Failed to plan operation {display_name}: {e}");
+                }
+                (None, Some(e.to_string()))
+            }
+        };
+        if args.json {
+            reports.push(OperationReport {
+                operation_name: operation_name.clone(),
+                error,
+                report,
+            });
+        }
+    }
+    if let Some(metrics_path) = &args.metrics {
+        metrics.write(metrics_path)?;
+    }
+
+    let checked = write_failure_report(&args, combination_failures)
+        .and_then(|()| check_max_sequential(&args, &sequential_violations))
+        .and_then(|()| check_max_distinct_plans(&args, &distinct_plans_violations))
+        .and_then(|()| check_deterministic(&nondeterministic))
+        .and_then(|()| {
+            if failures > 0 {
+                return Err(anyhow!(
+                    "{failures} of {} operations failed to plan",
+                    operations.len()
+                ));
+            }
+            Ok(())
+        });
+    if args.json {
+        // The error is part of the report, so that the output stays a single JSON document.
+        let report = OperationsReport {
+            operations: reports,
+            error: checked.as_ref().err().map(ErrorDetails::new),
+        };
+        output::write_json(&report)?;
+        return checked.map_err(|e| reported_in_json(true, e));
+    }
+    checked
+}
+
+/// Plans all combinations of override conditions for an operation, and filters and sorts the
+/// results as requested by the `plan` arguments.
+fn plan_operation(
+    args: &PlanArgs,
+    schema_str: &str,
+    query_str: &str,
+    config: QueryPlannerConfig,
+    operation_name: Option<&str>,
+) -> Result<PlanRun, AnyError> {
+    let query_path = source_name(args.query.as_path());
+    let cost_weights = CostWeights::from(&args.cost_args);
+    let mode = args.enumeration_mode();
+
+    // Build the baseline first, so that invalid baseline labels are reported before enumerating.
+    let baseline = if args.baseline_args.is_enabled() {
+        let baseline_states: Vec<_> = args
+            .baseline_args
+            .baseline
+            .iter()
+            .filter(|label| !label.is_empty())
+            .map(|label| (label.clone(), true))
+            .collect();
+        Some(
+            build_one_plan_with_states(
+                schema_str,
+                query_str,
+                query_path,
+                config.clone(),
+                operation_name,
+                &baseline_states,
+            )
+            .map_err(with_query_diagnostics)?,
+        )
+    } else {
         None
+    };
+    let mut builder = Analyzer::builder()
+        .schema(schema_str)
+        .query(query_str, query_path)
+        .planner_config(config.clone())
+        .enumeration_mode(mode);
+    if let Some(operation_name) = operation_name {
+        builder = builder.operation_name(operation_name);
+    }
+    let analyzer = builder.build().map_err(with_query_diagnostics)?;
+    let mut failures = Vec::new();
+    let (indices, results): (Vec<_>, Vec<_>) = if args.report_failures.is_some() {
+        // Failing combinations are set aside for the failure report, instead of failing the run.
+        let outcomes = analyzer.build_each_plan().map_err(with_query_diagnostics)?;
+        let mut results = Vec::new();
+        for (i, outcome) in outcomes.into_iter().enumerate() {
+            match outcome {
+                Ok(result) => results.push((i, result)),
+                Err(failure) => failures.push((i, failure)),
+            }
+        }
+        results.into_iter().unzip()
     } else {
-        Some(override_conditions)
+        analyzer
+            .build_all_plans()
+            .map_err(with_query_diagnostics)?
+            .into_iter()
+            .enumerate()
+            .unzip()
     };
-    let result = build_one_plan(
-        &read_input(schema_path),
-        &read_input(query_path),
-        query_path,
-        planner_args.into(),
-        override_all,
+    let nondeterministic = if args.verify_deterministic {
+        nondeterministic_combinations(&analyzer, &indices, &results)?
+    } else {
+        Vec::new()
+    };
+    // All combinations plan the same query document, hence share its warnings.
+    if let Some(result) = results.first() {
+        args.warning_args.check(&result.warnings)?;
+    }
+    let summary = PlanSummary::new(&results, &cost_weights);
+    let sequential_violations: Vec<_> = match args.max_sequential {
+        Some(max_sequential) => indices
+            .iter()
+            .zip(&results)
+            .filter_map(|(i, result)| {
+                let critical_path_length = result.stats().critical_path_length;
+                (critical_path_length > max_sequential).then(|| {
+                    format!(
+                        "Override Combination #{i}: {} requires {critical_path_length} sequential fetches",
+                        result.query_plan_config.combination_name
+                    )
+                })
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    let distinct_plans_violation = args
+        .max_distinct_plans
+        .filter(|max| summary.distinct_plans > *max)
+        .map(|max| {
+            format!(
+                "The combinations yield {} distinct query plans (at most {max} are allowed)",
+                summary.distinct_plans
+            )
+        });
+
+    // Keep the enumeration index of each combination, so that filtering doesn't renumber them.
+    let mut results: Vec<_> = indices.into_iter().zip(results).collect();
+    let mut note = None;
+    if let Some(baseline) = &baseline
+        && args.baseline_args.only_changed
+    {
+        let baseline_fingerprint = baseline.fingerprint();
+        let total = results.len();
+        results.retain(|(_, result)| result.fingerprint() != baseline_fingerprint);
+        note = Some(format!(
+            "{} of {total} combinations were filtered out, since their query plans are identical to the baseline {:?}",
+            total - results.len(),
+            baseline.query_plan_config.override_conditions,
+        ));
+    }
+
+    if let Some(sort_by) = args.sort_by {
+        let sort_key = |(i, result): &(usize, QueryPlanResult)| match sort_by {
+            SortKey::Cost => plan_cost(&result.stats(), &cost_weights),
+            SortKey::Fetches => result.stats().fetch_count as f64,
+            SortKey::Depth => result.stats().critical_path_length as f64,
+            SortKey::Index => *i as f64,
+        };
+        let mut keyed: Vec<_> = results
+            .into_iter()
+            .map(|entry| (sort_key(&entry), entry))
+            .collect();
+        // Note: The sort is stable, which preserves the enumeration order among equal keys.
+        keyed.sort_by(|(x, _), (y, _)| {
+            if args.sort_asc {
+                x.total_cmp(y)
+            } else {
+                y.total_cmp(x)
+            }
+        });
+        results = keyed.into_iter().map(|(_, entry)| entry).collect();
+    }
+
+    Ok(PlanRun {
+        baseline,
+        note,
+        summary,
+        results,
+        sequential_violations,
+        distinct_plans_violation,
+        failures,
+        nondeterministic,
+    })
+}
+
+/// Plans the combinations a second time (with `--verify-deterministic`), and describes those
+/// whose query plan differs from the first time (by fingerprint), with both query plans.
+fn nondeterministic_combinations(
+    analyzer: &Analyzer,
+    indices: &[usize],
+    results: &[QueryPlanResult],
+) -> Result<Vec<String>, AnyError> {
+    let second_run = analyzer.build_each_plan()?;
+    Ok(indices
+        .iter()
+        .zip(results)
+        .filter_map(|(i, first)| {
+            let combination_name = &first.query_plan_config.combination_name;
+            match &second_run[*i] {
+                Ok(second) if second.fingerprint() == first.fingerprint() => None,
+                Ok(second) => Some(format!(
+                    "Override Combination #{i}: {combination_name} yielded different query plans when planned twice\nFirst query plan:\n{}\nSecond query plan:\n{}",
+                    first.query_plan_display, second.query_plan_display
+                )),
+                Err(failure) => Some(format!(
+                    "Override Combination #{i}: {combination_name} failed to plan the second time: {}",
+                    failure.error
+                )),
+            }
+        })
+        .collect())
+}
+
+fn plan_report(args: &PlanArgs, run: PlanRun) -> PlanReport {
+    let cost_weights = CostWeights::from(&args.cost_args);
+    PlanReport {
+        baseline: run
+            .baseline
+            .map(|baseline| baseline.query_plan_config.override_conditions),
+        note: run.note,
+        summary: run.summary,
+        results: run
+            .results
+            .into_iter()
+            .map(|(_, result)| PlanEntry {
+                stats: args
+                    .cost_args
+                    .stats
+                    .then(|| PlanStats::new(&result, &cost_weights)),
+                result,
+            })
+            .collect(),
+        error: None,
+    }
+}
+
+/// Prints the results of a `plan` run in text format (and the note and summary to stderr).
+fn print_plan_run(args: &PlanArgs, schema_str: &str, run: &PlanRun) -> Result<(), AnyError> {
+    let cost_weights = CostWeights::from(&args.cost_args);
+    if let Some(note) = &run.note
+        && !args.quiet
+    {
+        eprintln!("{note}");
+    }
+    for (i, result) in &run.results {
+        let combination_name = &result.query_plan_config.combination_name;
+        outputln!("-----------------------------------------------------------------------")?;
+        outputln!("Override Combination #{i}: {combination_name}")?;
+        outputln!("-----------------------------------------------------------------------")?;
+        outputln!("{}\n", render_plan(result, &args.render_args))?;
+        if args.cost_args.stats {
+            outputln!("{}\n", PlanStats::new(result, &cost_weights))?;
+        }
+        if let Some(baseline) = &run.baseline
+            && args.baseline_args.show_diff
+        {
+            match qp_analyzer::compare_query_plans(schema_str, baseline, result) {
+                None => outputln!("(identical to the baseline)\n")?,
+                Some(QueryPlanDifference {
+                    full_diff,
+                    diff_description,
+                }) => {
+                    outputln!("Difference from the baseline:")?;
+                    outputln!("{}", SubgraphChanges::new(baseline, result))?;
+                    outputln!("{full_diff}")?;
+                    outputln!("{diff_description}\n")?;
+                }
+            }
+        }
+    }
+    if !args.quiet {
+        eprintln!("{}", run.summary);
+    }
+    Ok(())
+}
+
+/// A combination of override conditions listed by `plan --dry-run`
+#[derive(serde::Serialize)]
+struct DryRunCombination {
+    /// The enumeration index of the combination
+    index: usize,
+    override_conditions: Vec<String>,
+    combination_name: String,
+}
+
+/// Lists the combinations of override conditions that `plan` would plan, and their count.
+/// - The query planner isn't run, so only the supergraph schema is loaded.
+fn print_dry_run(args: &PlanArgs, schema_str: &str) -> Result<(), AnyError> {
+    let override_labels = get_override_labels(schema_str)?;
+    let combinations: Vec<_> =
+        OverrideCombinations::new(&override_labels, args.enumeration_mode())?
+            .enumerate()
+            .map(|(index, override_conditions)| DryRunCombination {
+                index,
+                combination_name: combination_name(&override_labels, &override_conditions),
+                override_conditions,
+            })
+            .collect();
+    if args.json {
+        return output::write_json(&combinations);
+    }
+    for combination in &combinations {
+        outputln!(
+            "Override Combination #{}: {}",
+            combination.index,
+            combination.combination_name
+        )?;
+    }
+    outputln!("{} combination(s) would be planned", combinations.len())
+}
+
+/// Fails if any combination exceeds `--max-sequential`, listing them.
+fn check_max_sequential(args: &PlanArgs, sequential_violations: &[String]) -> Result<(), AnyError> {
+    if let Some(max_sequential) = args.max_sequential
+        && !sequential_violations.is_empty()
+    {
+        for violation in sequential_violations {
+            eprintln!("{violation}");
+        }
+        return Err(anyhow!(
+            "{} combinations exceed the maximum of {max_sequential} sequential fetches",
+            sequential_violations.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Fails if any combination yielded different query plans when planned twice
+/// (`--verify-deterministic`), showing both query plans.
+fn check_deterministic(nondeterministic: &[String]) -> Result<(), AnyError> {
+    if nondeterministic.is_empty() {
+        return Ok(());
+    }
+    for description in nondeterministic {
+        eprintln!("{description}");
+    }
+    Err(anyhow!(
+        "{} combinations yielded different query plans when planned twice",
+        nondeterministic.len()
+    ))
+}
+
+/// Fails if any operation exceeds `--max-distinct-plans`, reporting its number of distinct query
+/// plans.
+fn check_max_distinct_plans(
+    args: &PlanArgs,
+    distinct_plans_violations: &[String],
+) -> Result<(), AnyError> {
+    let Some(max_distinct_plans) = args.max_distinct_plans else {
+        return Ok(());
+    };
+    match distinct_plans_violations {
+        [] => Ok(()),
+        [violation] => Err(anyhow!("{violation}")),
+        violations => {
+            for violation in violations {
+                eprintln!("{violation}");
+            }
+            Err(anyhow!(
+                "{} operations exceed the maximum of {max_distinct_plans} distinct query plans",
+                violations.len()
+            ))
+        }
+    }
+}
+
+/// JSON output of the `plan-manifest` command
+#[derive(serde::Serialize)]
+struct ManifestReport {
+    /// The results of each persisted operation, by its persisted query ID
+    operations: BTreeMap<String, ManifestEntry>,
+    /// The IDs of the persisted operations that failed to plan
+    failures: Vec<String>,
+}
+
+/// The results of a persisted operation in the JSON output of the `plan-manifest` command
+#[derive(serde::Serialize)]
+struct ManifestEntry {
+    /// The operation name, as listed in the manifest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// Why the operation could not be planned, if it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Statistics over all evaluated combinations (absent if the operation failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<PlanSummary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    results: Vec<QueryPlanResult>,
+}
+
+/// Plans every operation of a persisted query manifest, and reports the failing ones at the end.
+fn cmd_plan_manifest(
+    schema_path: &Path,
+    manifest_path: &Path,
+    json_output: bool,
+    enumerate: Enumeration,
+    sqlite_path: Option<&Path>,
+    metrics_path: Option<&Path>,
+    config: QueryPlannerConfig,
+) -> Result<(), AnyError> {
+    let [schema_str, manifest_str] = read_inputs([schema_path, manifest_path])?;
+    output::redact_routing_urls(&schema_str);
+    let manifest = parse_manifest(&manifest_str, manifest_path)?;
+    let cost_weights = CostWeights::default();
+    let mut sqlite = match sqlite_path {
+        Some(path) => Some(SqliteWriter::create(path, cost_weights)?),
+        None => None,
+    };
+
+    let planned: Vec<_> = manifest
+        .operations
+        .par_iter()
+        .map(|operation| {
+            // The manifest path and the ID locate the operation in diagnostics.
+            let query_path = format!("{}#{}", manifest_path.display(), operation.id);
+            build_all_plans(
+                &schema_str,
+                &operation.body,
+                query_path,
+                config.clone(),
+                enumerate.into(),
+            )
+        })
+        .collect();
+
+    let mut metrics = RunMetrics::default();
+    let mut report = ManifestReport {
+        operations: BTreeMap::new(),
+        failures: Vec::new(),
+    };
+    for (operation, results) in manifest.operations.into_iter().zip(planned) {
+        let entry = match results {
+            Ok(results) => {
+                if let Some(sqlite) = &mut sqlite {
+                    sqlite.insert(&operation.id, &results)?;
+                }
+                ManifestEntry {
+                    name: operation.name,
+                    error: None,
+                    summary: Some(PlanSummary::new(&results, &cost_weights)),
+                    results,
+                }
+            }
+            Err(e) => {
+                report.failures.push(operation.id.clone());
+                ManifestEntry {
+                    name: operation.name,
+                    error: Some(e.to_string()),
+                    summary: None,
+                    results: Vec::new(),
+                }
+            }
+        };
+        match &entry.summary {
+            Some(summary) => metrics.add(summary),
+            None => metrics.add_failure(),
+        }
+        report.operations.insert(operation.id, entry);
+    }
+    if let Some(metrics_path) = metrics_path {
+        metrics.write(metrics_path)?;
+    }
+
+    if json_output {
+        output::write_json(&report)?;
+    } else {
+        for (id, entry) in &report.operations {
+            outputln!("-----------------------------------------------------------------------")?;
+            match &entry.name {
+                Some(name) => outputln!("Persisted operation {id} ({name})")?,
+                None => outputln!("Persisted operation {id}")?,
+            }
+            outputln!("-----------------------------------------------------------------------")?;
+            match (&entry.summary, &entry.error) {
+                (Some(summary), _) => outputln!("{summary}\n")?,
+                (None, Some(error)) => outputln!("Error: {error}\n")?,
+                (None, None) => {}
+            }
+        }
+    }
+
+    let total = report.operations.len();
+    eprintln!(
+        "Planned {} of {total} persisted operations",
+        total - report.failures.len()
+    );
+    if !report.failures.is_empty() {
+        for id in &report.failures {
+            eprintln!("Failed: {id}");
+        }
+        // The failures are listed in the JSON report.
+        let error = anyhow!(
+            "{} persisted operations failed to plan",
+            report.failures.len()
+        );
+        return Err(reported_in_json(json_output, error));
+    }
+    Ok(())
+}
+
+fn cmd_build_one_plan(args: PlanOneArgs, config: QueryPlannerConfig) -> Result<(), AnyError> {
+    let PlanOneArgs {
+        schema: schema_path,
+        query: query_path,
         override_conditions,
-    )?;
+        conditions_file,
+        override_all,
+        json: json_output,
+        compare_type_conditioned,
+        render_args,
+        warning_args,
+        planner_args: _,
+    } = args;
+    let query_path = query_path.as_path();
+    let [schema_str, query_str] = read_inputs([schema_path.as_path(), query_path])?;
+    output::redact_routing_urls(&schema_str);
+    let query_path = source_name(query_path);
+    let plan = |config: QueryPlannerConfig| {
+        plan_one(
+            &schema_str,
+            &query_str,
+            query_path,
+            config,
+            &override_conditions,
+            conditions_file.as_deref(),
+            override_all,
+        )
+    };
+    if compare_type_conditioned {
+        // The two configurations differ only in type-conditioned fetching.
+        let mut config_off = config.clone();
+        config_off.type_conditioned_fetching = false;
+        let mut config_on = config;
+        config_on.type_conditioned_fetching = true;
+        let plan_off = plan(config_off)?;
+        let plan_on = plan(config_on)?;
+        warning_args.check(&plan_off.warnings)?;
+        return print_type_conditioned_comparison(&schema_str, &plan_off, &plan_on);
+    }
+    let result = plan(config)?;
+    warning_args.check(&result.warnings)?;
     if json_output {
-        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        output::write_json(&result)?;
     } else {
-        println!("{}", result.query_plan_display);
+        outputln!("{}", render_plan(&result, &render_args))?;
     }
     Ok(())
 }
 
-fn read_input(input_path: &Path) -> String {
-    if input_path == std::path::Path::new("-") {
-        io::read_to_string(io::stdin()).unwrap()
+/// Plans the query for the override conditions of the `plan-one` arguments.
+fn plan_one(
+    schema_str: &str,
+    query_str: &str,
+    query_path: &Path,
+    config: QueryPlannerConfig,
+    override_conditions: &[String],
+    conditions_file: Option<&Path>,
+    override_all: bool,
+) -> Result<QueryPlanResult, AnyError> {
+    let result = if override_all {
+        // Specific labels are passed along, so that the conflicting arguments are reported.
+        let override_conditions =
+            (!override_conditions.is_empty()).then(|| override_conditions.to_vec());
+        build_one_plan(
+            schema_str,
+            query_str,
+            query_path,
+            config,
+            override_all,
+            override_conditions,
+        )
+        .map_err(with_query_diagnostics)?
+    } else {
+        let mut condition_states = override_conditions
+            .iter()
+            .map(|token| parse_condition_state(token))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(conditions_file) = conditions_file {
+            condition_states.extend(read_conditions_file(conditions_file)?);
+        }
+        build_one_plan_with_states(
+            schema_str,
+            query_str,
+            query_path,
+            config,
+            None,
+            &condition_states,
+        )
+        .map_err(|e| {
+            let e = with_query_diagnostics(e);
+            // The combination is irrelevant to an invalid query.
+            if e.is::<InvalidQuery>() {
+                return e;
+            }
+            CombinationError {
+                override_conditions: condition_states
+                    .iter()
+                    .filter(|(_, enabled)| *enabled)
+                    .map(|(label, _)| label.clone())
+                    .collect(),
+                source: e,
+            }
+            .into()
+        })?
+    };
+    Ok(result)
+}
+
+/// Prints whether the query plans with `experimental_type_conditioned_fetching` off and on
+/// differ (`plan-one --compare-type-conditioned`), and how.
+fn print_type_conditioned_comparison(
+    schema_str: &str,
+    plan_off: &QueryPlanResult,
+    plan_on: &QueryPlanResult,
+) -> Result<(), AnyError> {
+    match qp_analyzer::compare_query_plans(schema_str, plan_off, plan_on) {
+        None => outputln!(
+            "The query plans are identical with type-conditioned fetching off and on.\n\n{}",
+            plan_off.query_plan_display
+        ),
+        Some(QueryPlanDifference {
+            full_diff,
+            diff_description,
+        }) => {
+            outputln!("The query plans differ with type-conditioned fetching off and on.")?;
+            outputln!("{}", SubgraphChanges::new(plan_off, plan_on))?;
+            outputln!("\nFull diff (off -> on):\n{full_diff}")?;
+            outputln!("{diff_description}")
+        }
+    }
+}
+
+/// Parses an override condition argument: either `label` (enabled), `label=true` or `label=false`.
+fn parse_condition_state(token: &str) -> Result<(String, bool), AnyError> {
+    let Some((label, value)) = token.rsplit_once('=') else {
+        return Ok((token.to_string(), true));
+    };
+    match value {
+        "true" => Ok((label.to_string(), true)),
+        "false" => Ok((label.to_string(), false)),
+        _ => Err(anyhow!(
+            "Invalid override condition `{token}`: expected `{label}=true` or `{label}=false`"
+        )),
+    }
+}
+
+/// Reads the state of override condition labels from a file.
+/// - The file is either a JSON object mapping labels to `true`/`false`, a JSON array of enabled
+///   labels, or lists one enabled label per line.
+/// - In the line format, empty lines and lines starting with `#` are ignored.
+fn read_conditions_file(path: &Path) -> Result<Vec<(String, bool)>, AnyError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read conditions file {}: {e}", path.display()))?;
+    let invalid_json =
+        |e: serde_json::Error| anyhow!("Invalid JSON in conditions file {}: {e}", path.display());
+    let content_start = content.trim_start();
+    if content_start.starts_with('{') {
+        let states: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(&content).map_err(invalid_json)?;
+        return states
+            .into_iter()
+            .map(|(label, state)| match state {
+                serde_json::Value::Bool(enabled) => Ok((label, enabled)),
+                _ => Err(anyhow!(
+                    "Invalid state of label {label} in conditions file {}: expected `true` or `false`, found {state}",
+                    path.display()
+                )),
+            })
+            .collect();
+    }
+    let labels: Vec<String> = if content_start.starts_with('[') {
+        serde_json::from_str(&content).map_err(invalid_json)?
     } else {
-        fs::read_to_string(input_path).unwrap()
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect()
+    };
+    Ok(labels.into_iter().map(|label| (label, true)).collect())
+}
+
+/// Renders the query plan in a text format.
+/// - The indentation applies to the formats rendered by this crate (the `text` format is the
+///   upstream query plan display, which has a fixed indentation).
+fn render_plan(result: &QueryPlanResult, args: &RenderArgs) -> String {
+    let RenderArgs {
+        format,
+        node_link,
+        indent,
+        annotate_subgraphs,
+    } = args;
+    match format {
+        OutputFormat::Text if *annotate_subgraphs => {
+            annotate_display_subgraphs(&result.query_plan_display)
+        }
+        OutputFormat::Text => result.query_plan_display.clone(),
+        OutputFormat::Tree => plan_to_tree_with_options(
+            &result.experimental_query_plan_serialized,
+            &TreeOptions {
+                indent: indent.map_or(DEFAULT_TREE_INDENT, usize::from),
+                annotate_subgraphs: *annotate_subgraphs,
+            },
+        ),
+        OutputFormat::Compact => plan_to_compact(&result.experimental_query_plan_serialized),
+        OutputFormat::Dot => plan_to_dot(&result.experimental_query_plan_serialized),
+        OutputFormat::Mermaid => plan_to_mermaid(
+            &result.experimental_query_plan_serialized,
+            node_link.as_deref(),
+        ),
+        OutputFormat::RouterJson => to_indented_json(
+            &plan_to_router_json(&result.experimental_query_plan_serialized),
+            *indent,
+        ),
+        OutputFormat::NodeJson => to_indented_json(
+            &plan_to_identified_nodes(&result.experimental_query_plan_serialized),
+            *indent,
+        ),
     }
 }
 
-fn cmd_compare_plans(schema_path: &Path, path_x: &Path, path_y: &Path) -> Result<(), AnyError> {
-    let schema_str = fs::read_to_string(schema_path)?;
+/// Pretty-prints the value as JSON, with `indent` spaces per level (2 by default).
+fn to_indented_json(value: &impl serde::Serialize, indent: Option<u8>) -> String {
+    let indent = " ".repeat(indent.map_or(2, usize::from));
+    let mut json = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut json, formatter);
+    // Serializing plan data to memory can't fail.
+    value.serialize(&mut serializer).unwrap();
+    String::from_utf8(json).unwrap()
+}
+
+fn cmd_compare_plans(
+    schema_path: &Path,
+    path_x: &Path,
+    path_y: &Path,
+    explain: bool,
+) -> Result<(), AnyError> {
+    let schema_str = read_input(schema_path)?;
+    output::redact_routing_urls(&schema_str);
     let plan_x: QueryPlanResult = serde_json::from_str(&fs::read_to_string(path_x)?)?;
     let plan_y: QueryPlanResult = serde_json::from_str(&fs::read_to_string(path_y)?)?;
     let result = qp_analyzer::compare_query_plans(&schema_str, &plan_x, &plan_y);
@@ -247,9 +2316,605 @@ fn cmd_compare_plans(schema_path: &Path, path_x: &Path, path_y: &Path) -> Result
             full_diff,
             diff_description,
         }) => {
-            eprintln!("\nFull diff:\n{}", full_diff);
+            if explain {
+                let changes = qp_analyzer::explain_plan_changes(
+                    &plan_x.experimental_query_plan_serialized,
+                    &plan_y.experimental_query_plan_serialized,
+                );
+                eprintln!("{changes}");
+            } else {
+                eprintln!("{}", SubgraphChanges::new(&plan_x, &plan_y));
+                eprintln!("\nFull diff:\n{}", full_diff);
+            }
             eprintln!("{}", diff_description);
             Err(anyhow!("The two query plans are different"))
         }
     }
 }
+
+fn cmd_assert_plan(
+    schema_path: &Path,
+    query_path: &Path,
+    expected_path: &Path,
+    override_conditions: &[String],
+    operation_name: Option<&str>,
+    config: QueryPlannerConfig,
+) -> Result<(), AnyError> {
+    let expected: QueryPlanResult = serde_json::from_str(&fs::read_to_string(expected_path)?)?;
+    let condition_states = override_conditions
+        .iter()
+        .map(|token| parse_condition_state(token))
+        .collect::<Result<Vec<_>, _>>()?;
+    let [schema_str, query_str] = read_inputs([schema_path, query_path])?;
+    output::redact_routing_urls(&schema_str);
+    let query_path = source_name(query_path);
+    let result = build_one_plan_with_states(
+        &schema_str,
+        &query_str,
+        query_path,
+        config,
+        operation_name,
+        &condition_states,
+    )
+    .map_err(with_query_diagnostics)?;
+    match qp_analyzer::compare_query_plans(&schema_str, &expected, &result) {
+        None => {
+            eprintln!("The query plan matches the expected query plan.");
+            Ok(())
+        }
+        Some(QueryPlanDifference {
+            full_diff,
+            diff_description,
+        }) => {
+            eprintln!("{}", SubgraphChanges::new(&expected, &result));
+            eprintln!("\nFull diff:\n{}", full_diff);
+            eprintln!("{}", diff_description);
+            Err(anyhow!(
+                "The query plan doesn't match the expected query plan {}",
+                expected_path.display()
+            ))
+        }
+    }
+}
+
+fn cmd_find_combo(
+    schema_path: &Path,
+    query_path: &Path,
+    target_path: Option<PathBuf>,
+    target_fingerprint: Option<String>,
+    config: QueryPlannerConfig,
+) -> Result<(), AnyError> {
+    let target_fingerprint = match (target_path, target_fingerprint) {
+        (_, Some(fingerprint)) => fingerprint,
+        (Some(target_path), None) => {
+            let target: QueryPlanResult = serde_json::from_str(&fs::read_to_string(target_path)?)?;
+            target.fingerprint()
+        }
+        (None, None) => return Err(anyhow!("Either a target file or a fingerprint is required")),
+    };
+    let [schema_str, query_str] = read_inputs([schema_path, query_path])?;
+    output::redact_routing_urls(&schema_str);
+    let query_path = source_name(query_path);
+    let result = minimal_override_set_for(
+        &schema_str,
+        &query_str,
+        query_path,
+        config,
+        &target_fingerprint,
+    )
+    .map_err(with_query_diagnostics)?;
+    match result {
+        Some(override_conditions) => {
+            outputln!("{override_conditions:?}")?;
+            Ok(())
+        }
+        None => Err(anyhow!(
+            "No combination of override conditions yields the target query plan"
+        )),
+    }
+}
+
+fn cmd_insensitive_labels(
+    schema_path: &Path,
+    query_path: &Path,
+    config: QueryPlannerConfig,
+) -> Result<(), AnyError> {
+    let [schema_str, query_str] = read_inputs([schema_path, query_path])?;
+    output::redact_routing_urls(&schema_str);
+    let query_path = source_name(query_path);
+    let labels = insensitive_override_labels(&schema_str, &query_str, query_path, config, None)
+        .map_err(with_query_diagnostics)?;
+    if labels.is_empty() {
+        eprintln!("Every override condition label can change the query plan.");
+    }
+    for label in labels {
+        outputln!("{label}")?;
+    }
+    Ok(())
+}
+
+/// JSON output of the `label-impact` command
+#[derive(serde::Serialize)]
+struct LabelImpactReport {
+    /// The number of analyzed operations (excluding the failed ones)
+    operation_count: usize,
+    /// Every override condition label, by decreasing number of affected operations
+    labels: Vec<LabelImpact>,
+    /// The operations that failed to plan
+    failures: Vec<OperationFailure>,
+}
+
+/// The operations whose query plans an override condition label affects
+#[derive(serde::Serialize)]
+struct LabelImpact {
+    label: String,
+    operation_count: usize,
+    operations: Vec<String>,
+}
+
+/// Runs the sensitivity analysis of `insensitive-labels` for every operation of the query files,
+/// and reports the number of operations each label affects.
+/// - Operations are named `<query file>#<operation name>` (or just `<query file>` for an
+///   anonymous operation).
+fn cmd_label_impact(
+    schema_path: &Path,
+    query_paths: &[PathBuf],
+    json_output: bool,
+    config: QueryPlannerConfig,
+) -> Result<(), AnyError> {
+    let OperationSet {
+        schema_str,
+        operations,
+        mut failures,
+    } = read_operation_set(schema_path, query_paths, None)?;
+    let override_labels = get_override_labels(&schema_str)?;
+
+    // The operations are analyzed concurrently (see `--jobs`).
+    let analyzed: Vec<_> = operations
+        .par_iter()
+        .map(|operation| {
+            insensitive_override_labels(
+                &schema_str,
+                &operation.query_str,
+                &operation.query_path,
+                config.clone(),
+                operation.operation_name.as_deref(),
+            )
+        })
+        .collect();
+
+    let mut labels: Vec<_> = override_labels
+        .iter()
+        .map(|label| LabelImpact {
+            label: label.to_string(),
+            operation_count: 0,
+            operations: Vec::new(),
+        })
+        .collect();
+    let mut operation_count = 0;
+    for (operation, insensitive) in operations.into_iter().zip(analyzed) {
+        match insensitive {
+            Ok(insensitive) => {
+                operation_count += 1;
+                for impact in &mut labels {
+                    if !insensitive.contains(&impact.label) {
+                        impact.operation_count += 1;
+                        impact.operations.push(operation.name.clone());
+                    }
+                }
+            }
+            Err(e) => failures.push(OperationFailure {
+                operation: operation.name,
+                error: e.to_string(),
+            }),
+        }
+    }
+    // Note: The sort is stable, which preserves the schema's label order among equal counts.
+    labels.sort_by(|x, y| y.operation_count.cmp(&x.operation_count));
+
+    let report = LabelImpactReport {
+        operation_count,
+        labels,
+        failures,
+    };
+    if json_output {
+        output::write_json(&report)?;
+    } else {
+        let width = report
+            .labels
+            .iter()
+            .map(|impact| impact.label.len())
+            .max()
+            .unwrap_or(0)
+            .max("LABEL".len());
+        outputln!("{:width$}  OPERATIONS", "LABEL")?;
+        for impact in &report.labels {
+            outputln!(
+                "{:width$}  {} of {operation_count}",
+                impact.label,
+                impact.operation_count
+            )?;
+        }
+    }
+
+    if !report.failures.is_empty() {
+        for failure in &report.failures {
+            eprintln!("Failed to plan {}: {}", failure.operation, failure.error);
+        }
+        // The failures are listed in the JSON report.
+        let error = anyhow!("{} operations failed to plan", report.failures.len());
+        return Err(reported_in_json(json_output, error));
+    }
+    Ok(())
+}
+
+/// JSON output of the `impacted-operations` command
+#[derive(serde::Serialize)]
+struct ImpactedOperationsReport {
+    label: String,
+    /// The number of analyzed operations (excluding the failed ones)
+    operation_count: usize,
+    /// The operations whose query plan changes when the label is toggled
+    impacted_operations: Vec<String>,
+    /// The operations that failed to plan
+    failures: Vec<OperationFailure>,
+}
+
+/// Plans every operation with the label off and on (and all other labels off), and lists the
+/// operations whose query plans differ.
+fn cmd_impacted_operations(
+    schema_path: &Path,
+    label: &str,
+    query_paths: &[PathBuf],
+    manifest_path: Option<&Path>,
+    json_output: bool,
+    config: QueryPlannerConfig,
+) -> Result<(), AnyError> {
+    let OperationSet {
+        schema_str,
+        operations,
+        mut failures,
+    } = read_operation_set(schema_path, query_paths, manifest_path)?;
+    // Validate the label before planning any operation.
+    let override_labels = get_override_labels(&schema_str)?;
+    if !override_labels.contains(label) {
+        return Err(anyhow!(
+            "Unknown override condition label: {label}. Available labels: {override_labels:?}"
+        ));
+    }
+
+    // The operations are analyzed concurrently (see `--jobs`).
+    let analyzed: Vec<_> = operations
+        .par_iter()
+        .map(|operation| {
+            let plan_with = |enabled: bool| {
+                build_one_plan_with_states(
+                    &schema_str,
+                    &operation.query_str,
+                    &operation.query_path,
+                    config.clone(),
+                    operation.operation_name.as_deref(),
+                    &[(label.to_string(), enabled)],
+                )
+            };
+            Ok::<_, AnyError>(plan_with(false)?.fingerprint() != plan_with(true)?.fingerprint())
+        })
+        .collect();
+
+    let mut report = ImpactedOperationsReport {
+        label: label.to_string(),
+        operation_count: 0,
+        impacted_operations: Vec::new(),
+        failures: Vec::new(),
+    };
+    for (operation, impacted) in operations.into_iter().zip(analyzed) {
+        match impacted {
+            Ok(impacted) => {
+                report.operation_count += 1;
+                if impacted {
+                    report.impacted_operations.push(operation.name);
+                }
+            }
+            Err(e) => failures.push(OperationFailure {
+                operation: operation.name,
+                error: e.to_string(),
+            }),
+        }
+    }
+    report.failures = failures;
+
+    if json_output {
+        output::write_json(&report)?;
+    } else {
+        for operation in &report.impacted_operations {
+            outputln!("{operation}")?;
+        }
+        eprintln!(
+            "{} of {} operations are impacted by {label}",
+            report.impacted_operations.len(),
+            report.operation_count
+        );
+    }
+
+    if !report.failures.is_empty() {
+        for failure in &report.failures {
+            eprintln!("Failed to plan {}: {}", failure.operation, failure.error);
+        }
+        // The failures are listed in the JSON report.
+        let error = anyhow!("{} operations failed to plan", report.failures.len());
+        return Err(reported_in_json(json_output, error));
+    }
+    Ok(())
+}
+
+fn cmd_diff_overrides(old_schema_path: &Path, new_schema_path: &Path) -> Result<(), AnyError> {
+    let [old_schema_str, new_schema_str] = read_inputs([old_schema_path, new_schema_path])?;
+    output::redact_routing_urls(&old_schema_str);
+    output::redact_routing_urls(&new_schema_str);
+    let old_labels = get_override_labels(&old_schema_str)?;
+    let new_labels = get_override_labels(&new_schema_str)?;
+    let print_labels = |title: &str, labels: Vec<&Arc<str>>| -> Result<(), AnyError> {
+        outputln!("{title} ({}):", labels.len())?;
+        for label in labels {
+            outputln!("  {label}")?;
+        }
+        Ok(())
+    };
+    print_labels(
+        "Only in the old schema",
+        old_labels.difference(&new_labels).collect(),
+    )?;
+    print_labels(
+        "Only in the new schema",
+        new_labels.difference(&old_labels).collect(),
+    )?;
+    print_labels(
+        "In both schemas",
+        old_labels.intersection(&new_labels).collect(),
+    )
+}
+
+/// Plans the query with the same override conditions under both schemas and compares the plans.
+fn cmd_diff_schema(
+    old_schema_path: &Path,
+    new_schema_path: &Path,
+    query_path: &Path,
+    override_conditions: Vec<String>,
+    config: QueryPlannerConfig,
+) -> Result<(), AnyError> {
+    let [old_schema_str, new_schema_str, query_str] =
+        read_inputs([old_schema_path, new_schema_path, query_path])?;
+    output::redact_routing_urls(&old_schema_str);
+    output::redact_routing_urls(&new_schema_str);
+    let query_path = source_name(query_path);
+    let condition_states = override_conditions
+        .iter()
+        .map(|token| parse_condition_state(token))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let plan_with = |schema_str: &str, schema_path: &Path| {
+        build_one_plan_with_states(
+            schema_str,
+            &query_str,
+            query_path,
+            config.clone(),
+            None,
+            &condition_states,
+        )
+        .map_err(|e| anyhow!("Failed to plan with {}: {e}", schema_path.display()))
+    };
+    let old_plan = plan_with(&old_schema_str, old_schema_path)?;
+    let new_plan = plan_with(&new_schema_str, new_schema_path)?;
+    match qp_analyzer::compare_query_plans(&new_schema_str, &old_plan, &new_plan) {
+        None => {
+            eprintln!("The query plans are identical under both schemas.");
+            Ok(())
+        }
+        Some(QueryPlanDifference {
+            full_diff,
+            diff_description,
+        }) => {
+            eprintln!("\nFull diff:\n{full_diff}");
+            eprintln!("{diff_description}");
+            Err(anyhow!("The query plans differ between the two schemas"))
+        }
+    }
+}
+
+/// JSON output of the `matrix` command
+#[derive(serde::Serialize)]
+struct MatrixReport {
+    /// The query plan of each combination of the varied options (the first one has them all off)
+    configs: Vec<MatrixEntry>,
+}
+
+/// The query plan of a combination of query planner options, in the output of `matrix`
+#[derive(serde::Serialize)]
+struct MatrixEntry {
+    /// The state of each varied option
+    options: BTreeMap<&'static str, bool>,
+    fingerprint: String,
+    stats: PlanStats,
+    /// The changes from the query plan of the first combination (absent if identical)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    changes: Option<PlanChanges>,
+    query_plan_display: String,
+}
+
+/// Plans the query with the same override conditions under every combination of the varied
+/// query planner options (2^n combinations for n options), and compares each query plan with the
+/// one of the first combination (all options off).
+/// - The other options keep the values of the query planner arguments.
+fn cmd_matrix(
+    schema_path: &Path,
+    query_path: &Path,
+    override_conditions: Vec<String>,
+    vary: Vec<ConfigAxis>,
+    json_output: bool,
+    base_config: QueryPlannerConfig,
+) -> Result<(), AnyError> {
+    let [schema_str, query_str] = read_inputs([schema_path, query_path])?;
+    output::redact_routing_urls(&schema_str);
+    let query_path = source_name(query_path);
+    let condition_states = override_conditions
+        .iter()
+        .map(|token| parse_condition_state(token))
+        .collect::<Result<Vec<_>, _>>()?;
+    let axes: Vec<_> = if vary.is_empty() {
+        ConfigAxis::ALL.to_vec()
+    } else {
+        ConfigAxis::ALL
+            .into_iter()
+            .filter(|axis| vary.contains(axis))
+            .collect()
+    };
+
+    let mut entries: Vec<MatrixEntry> = Vec::new();
+    let mut first_plan: Option<QueryPlanResult> = None;
+    for mask in 0..1usize << axes.len() {
+        let mut config = base_config.clone();
+        let mut options = BTreeMap::new();
+        for (i, axis) in axes.iter().enumerate() {
+            let enabled = mask & (1 << i) != 0;
+            axis.set(&mut config, enabled);
+            options.insert(axis.name(), enabled);
+        }
+        let result = build_one_plan_with_states(
+            &schema_str,
+            &query_str,
+            query_path,
+            config,
+            None,
+            &condition_states,
+        )
+        .map_err(with_query_diagnostics)?;
+        let changes = first_plan.as_ref().and_then(|first| {
+            (first.fingerprint() != result.fingerprint()).then(|| {
+                qp_analyzer::explain_plan_changes(
+                    &first.experimental_query_plan_serialized,
+                    &result.experimental_query_plan_serialized,
+                )
+            })
+        });
+        entries.push(MatrixEntry {
+            options,
+            fingerprint: result.fingerprint(),
+            stats: PlanStats::new(&result, &CostWeights::default()),
+            changes,
+            query_plan_display: result.query_plan_display.clone(),
+        });
+        first_plan.get_or_insert(result);
+    }
+
+    if json_output {
+        return output::write_json(&MatrixReport { configs: entries });
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        let options: Vec<_> = entry
+            .options
+            .iter()
+            .map(|(name, enabled)| format!("{name}={}", if *enabled { "on" } else { "off" }))
+            .collect();
+        outputln!("-----------------------------------------------------------------------")?;
+        outputln!("Config #{i}: {{{}}}", options.join(", "))?;
+        outputln!("-----------------------------------------------------------------------")?;
+        outputln!("{}", entry.stats)?;
+        match (i, &entry.changes) {
+            (0, _) => {}
+            (_, None) => outputln!("Identical to Config #0")?,
+            (_, Some(changes)) => outputln!("Changes from Config #0:\n{changes}")?,
+        }
+        outputln!("\n{}", entry.query_plan_display)?;
+    }
+    let distinct: BTreeSet<_> = entries.iter().map(|entry| &entry.fingerprint).collect();
+    eprintln!(
+        "{} distinct query plans among {} configs",
+        distinct.len(),
+        entries.len()
+    );
+    Ok(())
+}
+
+/// Plans the query repeatedly and prints statistics of the planning time of each iteration.
+/// - Only the time spent in the query planner is measured (not parsing the schema and query).
+/// - Each iteration plans either the given combination or all combinations.
+fn cmd_bench(
+    schema_path: &Path,
+    query_path: &Path,
+    override_conditions: Vec<String>,
+    all_combinations: bool,
+    iterations: u32,
+    warmup: u32,
+    config: QueryPlannerConfig,
+) -> Result<(), AnyError> {
+    if iterations == 0 {
+        return Err(anyhow!("`--iterations` must be at least 1"));
+    }
+    let total_iterations = warmup.checked_add(iterations).ok_or_else(|| {
+        anyhow!(
+            "`--warmup` plus `--iterations` must be at most {}",
+            u32::MAX
+        )
+    })?;
+    let [schema_str, query_str] = read_inputs([schema_path, query_path])?;
+    output::redact_routing_urls(&schema_str);
+    let query_path = source_name(query_path);
+    let condition_states = override_conditions
+        .iter()
+        .map(|token| parse_condition_state(token))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut durations_ms = Vec::new();
+    for iteration in 0..total_iterations {
+        let results = if all_combinations {
+            build_all_plans(
+                &schema_str,
+                &query_str,
+                query_path,
+                config.clone(),
+                EnumerationMode::All,
+            )?
+        } else {
+            vec![build_one_plan_with_states(
+                &schema_str,
+                &query_str,
+                query_path,
+                config.clone(),
+                None,
+                &condition_states,
+            )?]
+        };
+        if iteration >= warmup {
+            durations_ms.push(
+                results
+                    .iter()
+                    .map(|result| result.planning_duration_ms)
+                    .sum(),
+            );
+        }
+    }
+
+    durations_ms.sort_by(f64::total_cmp);
+    let mean = durations_ms.iter().sum::<f64>() / durations_ms.len() as f64;
+    outputln!(
+        "{iterations} iterations ({warmup} warmup): min {:.3}ms, mean {mean:.3}ms, median {:.3}ms, max {:.3}ms",
+        durations_ms[0],
+        summary::percentile(&durations_ms, 50),
+        durations_ms[durations_ms.len() - 1],
+    )?;
+    Ok(())
+}
+
+fn cmd_api_schema(schema_path: &Path, config: QueryPlannerConfig) -> Result<(), AnyError> {
+    let schema_str = read_input(schema_path)?;
+    output::redact_routing_urls(&schema_str);
+    let sdl = api_schema_sdl(&schema_str, config)?;
+    output!("{sdl}")?;
+    Ok(())
+}
+
+fn cmd_print_output_schema() -> Result<(), AnyError> {
+    let schema = qp_analyzer::query_plan_result_schema();
+    output::write_json(&schema)?;
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use anyhow::Error as AnyError;
+use anyhow::anyhow;
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// An Apollo persisted query manifest
+/// - Only the fields needed for planning are read.
+#[derive(serde::Deserialize)]
+pub(crate) struct PersistedQueryManifest {
+    pub(crate) operations: Vec<PersistedOperation>,
+}
+
+/// An operation of a persisted query manifest
+#[derive(serde::Deserialize)]
+pub(crate) struct PersistedOperation {
+    /// The persisted query ID clients send instead of the operation document
+    pub(crate) id: String,
+    /// The operation document
+    pub(crate) body: String,
+    #[serde(default)]
+    pub(crate) name: Option<String>,
+}
+
+/// Parses a persisted query manifest (`{"operations": [{"id": ..., "body": ...}, ...]}`).
+/// - Persisted query IDs must be unique.
+pub(crate) fn parse_manifest(
+    content: &str,
+    manifest_path: &Path,
+) -> Result<PersistedQueryManifest, AnyError> {
+    let manifest: PersistedQueryManifest = serde_json::from_str(content).map_err(|e| {
+        anyhow!(
+            "Invalid persisted query manifest {}: {e}",
+            manifest_path.display()
+        )
+    })?;
+    // Note: The results are keyed by persisted query ID, so duplicates would overwrite each other.
+    let mut ids = HashSet::new();
+    let duplicates: BTreeSet<_> = manifest
+        .operations
+        .iter()
+        .map(|operation| operation.id.as_str())
+        .filter(|id| !ids.insert(*id))
+        .collect();
+    if !duplicates.is_empty() {
+        let duplicates: Vec<_> = duplicates.into_iter().collect();
+        return Err(anyhow!(
+            "Duplicate persisted query IDs in {}: {}",
+            manifest_path.display(),
+            duplicates.join(", ")
+        ));
+    }
+    Ok(manifest)
+}
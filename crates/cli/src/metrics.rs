@@ -0,0 +1,93 @@
+use anyhow::Error as AnyError;
+use anyhow::anyhow;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::summary::PlanSummary;
+
+/// Aggregate metrics of a planning run, written in the Prometheus text exposition format
+/// - Counts are summed over the planned operations.
+#[derive(Default)]
+pub(crate) struct RunMetrics {
+    operations: usize,
+    combinations: usize,
+    distinct_plans: usize,
+    planning_seconds_total: f64,
+    planning_seconds_max: f64,
+    failures: usize,
+}
+
+impl RunMetrics {
+    /// Adds the statistics of an operation's evaluated combinations.
+    pub(crate) fn add(&mut self, summary: &PlanSummary) {
+        self.operations += 1;
+        self.combinations += summary.combinations;
+        self.distinct_plans += summary.distinct_plans;
+        if let Some(timing) = &summary.timing {
+            self.planning_seconds_total += timing.total_ms / 1000.0;
+            self.planning_seconds_max = self.planning_seconds_max.max(timing.max_ms / 1000.0);
+        }
+    }
+
+    /// Counts an operation that failed to plan.
+    pub(crate) fn add_failure(&mut self) {
+        self.failures += 1;
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> Result<(), AnyError> {
+        fs::write(path, self.to_string())
+            .map_err(|e| anyhow!("Failed to write {}: {e}", path.display()))
+    }
+}
+
+impl fmt::Display for RunMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let metrics: [(&str, &str, &str, f64); 6] = [
+            (
+                "qp_analyzer_operations_total",
+                "counter",
+                "Number of planned operations",
+                self.operations as f64,
+            ),
+            (
+                "qp_analyzer_combinations_total",
+                "counter",
+                "Number of evaluated override condition combinations",
+                self.combinations as f64,
+            ),
+            (
+                "qp_analyzer_distinct_plans_total",
+                "counter",
+                "Number of distinct query plans, summed over operations",
+                self.distinct_plans as f64,
+            ),
+            (
+                "qp_analyzer_planning_seconds_total",
+                // A gauge, since the metrics describe a single run (the value doesn't accumulate
+                // across runs).
+                "gauge",
+                "Total time spent building query plans in the run",
+                self.planning_seconds_total,
+            ),
+            (
+                "qp_analyzer_planning_seconds_max",
+                "gauge",
+                "Longest time spent building a single query plan",
+                self.planning_seconds_max,
+            ),
+            (
+                "qp_analyzer_failures_total",
+                "counter",
+                "Number of operations that failed to plan",
+                self.failures as f64,
+            ),
+        ];
+        for (name, metric_type, help, value) in metrics {
+            writeln!(f, "# HELP {name} {help}")?;
+            writeln!(f, "# TYPE {name} {metric_type}")?;
+            writeln!(f, "{name} {value}")?;
+        }
+        Ok(())
+    }
+}
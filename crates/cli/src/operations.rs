@@ -0,0 +1,105 @@
+use anyhow::Error as AnyError;
+use qp_analyzer::operation_names;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::input::expand_query_paths;
+use crate::input::read_input_list;
+use crate::input::source_name;
+use crate::manifest::parse_manifest;
+use crate::output;
+
+/// An operation of a query file or of a persisted query manifest
+pub(crate) struct OperationSource {
+    /// How the operation is named in reports
+    /// - `<query file>#<operation name>` (or just `<query file>` for an anonymous operation), or
+    ///   the persisted query ID for the operations of a manifest.
+    pub(crate) name: String,
+    /// Where the operation comes from (for diagnostics)
+    pub(crate) query_path: String,
+    pub(crate) query_str: String,
+    /// The operation to plan, if the document defines several operations
+    pub(crate) operation_name: Option<String>,
+}
+
+/// Names an operation of a query file in reports: `<query file>#<operation name>`, or just
+/// `<query file>` for an anonymous (or the only) operation.
+pub(crate) fn operation_display_name(query_path: &Path, operation_name: Option<&str>) -> String {
+    match operation_name {
+        Some(name) => format!("{}#{name}", query_path.display()),
+        None => query_path.display().to_string(),
+    }
+}
+
+/// An operation that failed to parse or to plan
+#[derive(serde::Serialize)]
+pub(crate) struct OperationFailure {
+    pub(crate) operation: String,
+    pub(crate) error: String,
+}
+
+/// The supergraph schema and the operations of a batch command
+pub(crate) struct OperationSet {
+    pub(crate) schema_str: String,
+    pub(crate) operations: Vec<OperationSource>,
+    /// The query files that failed to parse
+    pub(crate) failures: Vec<OperationFailure>,
+}
+
+/// Reads the supergraph schema, and collects the operations of the query files (or directories of
+/// query files) and of the persisted query manifest, if any.
+/// - Every operation of a multi-operation query file is collected.
+pub(crate) fn read_operation_set(
+    schema_path: &Path,
+    query_paths: &[PathBuf],
+    manifest_path: Option<&Path>,
+) -> Result<OperationSet, AnyError> {
+    let query_paths = expand_query_paths(query_paths)?;
+    let mut input_paths = vec![schema_path];
+    input_paths.extend(manifest_path);
+    input_paths.extend(query_paths.iter().map(PathBuf::as_path));
+    let mut inputs = read_input_list(&input_paths)?.into_iter();
+    let mut next_input = || inputs.next().expect("one input is read per path");
+
+    let schema_str = next_input();
+    output::redact_routing_urls(&schema_str);
+    let mut operations = Vec::new();
+    let mut failures = Vec::new();
+    if let Some(manifest_path) = manifest_path {
+        let manifest = parse_manifest(&next_input(), manifest_path)?;
+        operations.extend(
+            manifest
+                .operations
+                .into_iter()
+                .map(|operation| OperationSource {
+                    query_path: format!("{}#{}", manifest_path.display(), operation.id),
+                    name: operation.id,
+                    query_str: operation.body,
+                    operation_name: None,
+                }),
+        );
+    }
+    for query_path in &query_paths {
+        let query_str = next_input();
+        let query_path = source_name(query_path);
+        match operation_names(&query_str, query_path) {
+            Ok(names) => {
+                operations.extend(names.into_iter().map(|operation_name| OperationSource {
+                    name: operation_display_name(query_path, operation_name.as_deref()),
+                    query_path: query_path.display().to_string(),
+                    query_str: query_str.clone(),
+                    operation_name,
+                }))
+            }
+            Err(e) => failures.push(OperationFailure {
+                operation: query_path.display().to_string(),
+                error: e.to_string(),
+            }),
+        }
+    }
+    Ok(OperationSet {
+        schema_str,
+        operations,
+        failures,
+    })
+}
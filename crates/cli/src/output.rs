@@ -0,0 +1,127 @@
+use anyhow::Error as AnyError;
+use anyhow::anyhow;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use qp_analyzer::list_subgraphs;
+
+use crate::redact::RedactingWriter;
+use crate::redact::RoutingUrls;
+
+/// The destination of the primary output of commands (set once from the command line)
+/// - Diagnostics, notes and logs go to stderr regardless.
+static OUTPUT: OnceLock<Mutex<Output>> = OnceLock::new();
+
+struct Output {
+    /// The name of the destination in error messages
+    name: String,
+    writer: Box<dyn Write + Send>,
+    /// The routing URLs redacted from the output (with `--redact-urls`)
+    routing_urls: Option<RoutingUrls>,
+}
+
+fn output() -> &'static Mutex<Output> {
+    OUTPUT.get_or_init(|| {
+        Mutex::new(Output {
+            name: "stdout".to_string(),
+            // Buffered, since JSON output is serialized in many small writes.
+            writer: Box::new(BufWriter::new(io::stdout())),
+            routing_urls: None,
+        })
+    })
+}
+
+/// Sets up the primary output: the file (created or truncated) or stdout.
+/// - With `redact_urls`, the routing URLs of the supergraph schemas read by the command are
+///   redacted from the output (see `redact_routing_urls`).
+pub(crate) fn init_output(path: Option<&Path>, redact_urls: bool) -> Result<(), AnyError> {
+    let (name, writer): (_, Box<dyn Write + Send>) = match path {
+        Some(path) => {
+            let file = File::create(path)
+                .map_err(|e| anyhow!("Failed to create output file {}: {e}", path.display()))?;
+            (path.display().to_string(), Box::new(BufWriter::new(file)))
+        }
+        None => ("stdout".to_string(), Box::new(BufWriter::new(io::stdout()))),
+    };
+    let routing_urls = redact_urls.then(RoutingUrls::default);
+    let writer = match &routing_urls {
+        Some(urls) => Box::new(RedactingWriter::new(writer, urls.clone())),
+        None => writer,
+    };
+    let output = Output {
+        name,
+        writer,
+        routing_urls,
+    };
+    OUTPUT
+        .set(Mutex::new(output))
+        .map_err(|_| anyhow!("The output destination is already set"))
+}
+
+/// Redacts the subgraph routing URLs of the supergraph schema from the output (with
+/// `--redact-urls`), from now on.
+/// - Schemas that fail to load are skipped, since the command reports their errors.
+pub(crate) fn redact_routing_urls(schema_str: &str) {
+    let routing_urls = {
+        let output = output().lock().unwrap_or_else(|e| e.into_inner());
+        output.routing_urls.clone()
+    };
+    let Some(routing_urls) = routing_urls else {
+        return;
+    };
+    if let Ok(subgraphs) = list_subgraphs(schema_str) {
+        routing_urls.extend(subgraphs.into_iter().filter_map(|subgraph| subgraph.url));
+    }
+}
+
+/// Runs the function with the writer of the primary output.
+pub(crate) fn with_output<T>(
+    f: impl FnOnce(&mut dyn Write) -> io::Result<T>,
+) -> Result<T, AnyError> {
+    // Note: The output stays usable even if a thread panicked while writing.
+    let mut output = output().lock().unwrap_or_else(|e| e.into_inner());
+    let Output { name, writer, .. } = &mut *output;
+    f(writer.as_mut()).map_err(|e| anyhow!("Failed to write to {name}: {e}"))
+}
+
+/// Writes formatted text to the primary output (see `output!` and `outputln!`).
+pub(crate) fn write_output(args: fmt::Arguments<'_>) -> Result<(), AnyError> {
+    with_output(|writer| writer.write_fmt(args))
+}
+
+/// Serializes the value as pretty-printed JSON (followed by a newline) to the primary output.
+/// - The JSON text is streamed to the writer, rather than built in memory first.
+pub(crate) fn write_json(value: &impl serde::Serialize) -> Result<(), AnyError> {
+    with_output(|writer| {
+        serde_json::to_writer_pretty(&mut *writer, value)?;
+        writer.write_all(b"\n")
+    })
+}
+
+/// Flushes the primary output (which is buffered).
+pub(crate) fn flush_output() -> Result<(), AnyError> {
+    with_output(|writer| writer.flush())
+}
+
+/// Like `print!`, but writes to the primary output and returns write errors.
+macro_rules! output {
+    ($($arg:tt)*) => {
+        $crate::output::write_output(format_args!($($arg)*))
+    };
+}
+
+/// Like `println!`, but writes to the primary output and returns write errors.
+macro_rules! outputln {
+    () => {
+        $crate::output::write_output(format_args!("\n"))
+    };
+    ($($arg:tt)*) => {
+        $crate::output::write_output(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
@@ -0,0 +1,146 @@
+use std::io;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// The replacement of redacted URLs
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// The subgraph routing URLs to redact, as registered by the commands reading supergraph schemas
+/// (see `output::redact_routing_urls`)
+#[derive(Clone, Default)]
+pub(crate) struct RoutingUrls(Arc<Mutex<Vec<String>>>);
+
+impl RoutingUrls {
+    pub(crate) fn extend(&self, urls: impl IntoIterator<Item = String>) {
+        let mut known = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        for url in urls {
+            if !url.is_empty() && !known.contains(&url) {
+                known.push(url);
+            }
+        }
+    }
+}
+
+/// Replaces the occurrences of the routing URLs in the text with `<redacted>`.
+/// - Other URLs (like the `@link` URLs of specifications) are kept.
+/// - Where several URLs match (one being a prefix of the other), the longest one is redacted.
+pub(crate) fn redact_urls(text: &str, urls: &[String]) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+    // The first occurrence of any URL, and the longest URL at that position
+    while let Some((start, len)) = urls
+        .iter()
+        .filter_map(|url| Some((rest.find(url.as_str())?, url.len())))
+        .min_by(|(a_start, a_len), (b_start, b_len)| a_start.cmp(b_start).then(b_len.cmp(a_len)))
+    {
+        redacted.push_str(&rest[..start]);
+        redacted.push_str(REDACTED);
+        rest = &rest[start + len..];
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+/// A writer redacting the routing URLs of the text written through it (see `redact_urls`).
+/// - The text is redacted line by line, so that URLs split over several writes are still
+///   redacted. Incomplete lines are written on flush.
+pub(crate) struct RedactingWriter<W: Write> {
+    inner: W,
+    urls: RoutingUrls,
+    line: Vec<u8>,
+}
+
+impl<W: Write> RedactingWriter<W> {
+    pub(crate) fn new(inner: W, urls: RoutingUrls) -> Self {
+        RedactingWriter {
+            inner,
+            urls,
+            line: Vec::new(),
+        }
+    }
+
+    fn write_line(&mut self) -> io::Result<()> {
+        let line = String::from_utf8_lossy(&self.line);
+        let urls = self.urls.0.lock().unwrap_or_else(|e| e.into_inner());
+        self.inner.write_all(redact_urls(&line, &urls).as_bytes())?;
+        self.line.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            self.line.push(byte);
+            if byte == b'\n' {
+                self.write_line()?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_line()?;
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls() -> Vec<String> {
+        let urls = RoutingUrls::default();
+        urls.extend([
+            "https://accounts.internal:4001/graphql".to_string(),
+            "https://accounts.internal:4001".to_string(),
+            "http://products".to_string(),
+        ]);
+        urls.0.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn text_without_routing_urls_is_kept() {
+        let text = "no scheme here, just `://` and https://specs.apollo.dev/link/v1.0";
+        assert_eq!(redact_urls(text, &urls()), text);
+    }
+
+    #[test]
+    fn routing_url_at_the_start() {
+        assert_eq!(
+            redact_urls("https://accounts.internal:4001/graphql (accounts)", &urls()),
+            "<redacted> (accounts)"
+        );
+    }
+
+    #[test]
+    fn routing_url_at_the_end() {
+        assert_eq!(
+            redact_urls("products -> http://products", &urls()),
+            "products -> <redacted>"
+        );
+    }
+
+    #[test]
+    fn non_ascii_text_before_routing_url() {
+        assert_eq!(
+            redact_urls(
+                "überprüft: \"https://accounts.internal:4001/graphql\"",
+                &urls()
+            ),
+            "überprüft: \"<redacted>\""
+        );
+    }
+
+    #[test]
+    fn longest_routing_url_is_redacted_first() {
+        assert_eq!(
+            redact_urls(
+                "https://accounts.internal:4001, https://accounts.internal:4001/graphql",
+                &urls()
+            ),
+            "<redacted>, <redacted>"
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use anyhow::Error as AnyError;
+use anyhow::anyhow;
+use qp_analyzer::CostWeights;
+use qp_analyzer::QueryPlanResult;
+use std::path::Path;
+
+/// Writes query plan results as rows of the `query_plans` table of a SQLite database.
+/// - The table is recreated, so that the database only holds the results of the current run.
+#[cfg(feature = "sqlite")]
+pub(crate) struct SqliteWriter {
+    connection: rusqlite::Connection,
+    weights: CostWeights,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteWriter {
+    pub(crate) fn create(path: &Path, weights: CostWeights) -> Result<Self, AnyError> {
+        let error = |e: rusqlite::Error| anyhow!("Failed to write {}: {e}", path.display());
+        let connection = rusqlite::Connection::open(path).map_err(error)?;
+        connection
+            .execute_batch(
+                "DROP TABLE IF EXISTS query_plans;
+                CREATE TABLE query_plans (
+                    operation TEXT NOT NULL,
+                    combination_mask INTEGER NOT NULL,
+                    combination_name TEXT NOT NULL,
+                    fetch_count INTEGER NOT NULL,
+                    subgraph_count INTEGER NOT NULL,
+                    max_depth INTEGER NOT NULL,
+                    cost REAL NOT NULL,
+                    fingerprint TEXT NOT NULL
+                );",
+            )
+            .map_err(error)?;
+        Ok(SqliteWriter {
+            connection,
+            weights,
+        })
+    }
+
+    /// Inserts a row for each query plan result of the operation.
+    pub(crate) fn insert<'a>(
+        &mut self,
+        operation: &str,
+        results: impl IntoIterator<Item = &'a QueryPlanResult>,
+    ) -> Result<(), AnyError> {
+        let error = |e: rusqlite::Error| anyhow!("Failed to write the results of {operation}: {e}");
+        let transaction = self.connection.transaction().map_err(error)?;
+        {
+            let mut statement = transaction
+                .prepare("INSERT INTO query_plans VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)")
+                .map_err(error)?;
+            for result in results {
+                let stats = result.stats();
+                statement
+                    .execute(rusqlite::params![
+                        operation,
+                        // SQLite integers are signed: masks with the 64th bit set become negative.
+                        result.query_plan_config.combination_mask as i64,
+                        result.query_plan_config.combination_name,
+                        stats.fetch_count as i64,
+                        result.subgraphs().len() as i64,
+                        stats.max_depth as i64,
+                        qp_analyzer::plan_cost(&stats, &self.weights),
+                        result.fingerprint(),
+                    ])
+                    .map_err(error)?;
+            }
+        }
+        transaction.commit().map_err(error)
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub(crate) struct SqliteWriter;
+
+#[cfg(not(feature = "sqlite"))]
+impl SqliteWriter {
+    pub(crate) fn create(path: &Path, _weights: CostWeights) -> Result<Self, AnyError> {
+        Err(anyhow!(
+            "Writing {} requires building with the `sqlite` feature",
+            path.display()
+        ))
+    }
+
+    pub(crate) fn insert<'a>(
+        &mut self,
+        _operation: &str,
+        _results: impl IntoIterator<Item = &'a QueryPlanResult>,
+    ) -> Result<(), AnyError> {
+        unreachable!("a SqliteWriter can't be created without the `sqlite` feature")
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+use std::fmt;
+
+use qp_analyzer::CostWeights;
+use qp_analyzer::QueryPlanResult;
+use qp_analyzer::QueryPlanStats;
+use qp_analyzer::plan_cost;
+
+/// Aggregate statistics over the results of a `plan` run
+#[derive(serde::Serialize)]
+pub(crate) struct PlanSummary {
+    /// The number of evaluated override condition combinations
+    pub(crate) combinations: usize,
+    /// The number of distinct query plans (by fingerprint) among them
+    pub(crate) distinct_plans: usize,
+    /// Planning time statistics (absent if no combination was evaluated)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) timing: Option<TimingSummary>,
+    pub(crate) subgraphs: SubgraphCoverage,
+    /// The combination with the lowest-cost query plan (absent if no combination was evaluated)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) cheapest: Option<CombinationCost>,
+    /// The combination with the highest-cost query plan (absent if no combination was evaluated)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) most_expensive: Option<CombinationCost>,
+    /// The number of combinations by the nesting depth (`max_depth`) of their query plans
+    pub(crate) depth_histogram: BTreeMap<usize, usize>,
+}
+
+/// A combination of override conditions and the cost of its query plan
+#[derive(serde::Serialize)]
+pub(crate) struct CombinationCost {
+    pub(crate) combination_name: String,
+    pub(crate) override_conditions: Vec<String>,
+    pub(crate) cost: f64,
+}
+
+impl CombinationCost {
+    fn new(result: &QueryPlanResult, cost: f64) -> Self {
+        CombinationCost {
+            combination_name: result.query_plan_config.combination_name.clone(),
+            override_conditions: result.query_plan_config.override_conditions.clone(),
+            cost,
+        }
+    }
+}
+
+/// The subgraphs fetched from by the query plans of the evaluated combinations
+#[derive(serde::Serialize)]
+pub(crate) struct SubgraphCoverage {
+    /// The subgraphs fetched from by any query plan
+    pub(crate) all: BTreeSet<String>,
+    /// The subgraphs fetched from by some query plans, but not others
+    /// - Whether these subgraphs are involved depends on the override conditions.
+    pub(crate) override_dependent: BTreeSet<String>,
+}
+
+/// Planning time statistics over the evaluated combinations, in milliseconds
+#[derive(serde::Serialize)]
+pub(crate) struct TimingSummary {
+    pub(crate) total_ms: f64,
+    pub(crate) mean_ms: f64,
+    pub(crate) p50_ms: f64,
+    pub(crate) p95_ms: f64,
+    pub(crate) max_ms: f64,
+    /// The name of the combination that took the longest to plan
+    pub(crate) slowest_combination: String,
+}
+
+impl PlanSummary {
+    pub(crate) fn new(results: &[QueryPlanResult], weights: &CostWeights) -> Self {
+        let fingerprints: HashSet<_> = results.iter().map(|result| result.fingerprint()).collect();
+        let costs: Vec<_> = results
+            .iter()
+            .map(|result| (result, plan_cost(&result.stats(), weights)))
+            .collect();
+        // Among equal costs, the first combination in enumeration order is reported.
+        // (`min_by` returns the first minimum, and `max_by` the last maximum, hence the `rev`.)
+        let cheapest = costs
+            .iter()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(result, cost)| CombinationCost::new(result, *cost));
+        let most_expensive = costs
+            .iter()
+            .rev()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(result, cost)| CombinationCost::new(result, *cost));
+        let mut depth_histogram = BTreeMap::new();
+        for result in results {
+            *depth_histogram.entry(result.stats().max_depth).or_default() += 1;
+        }
+        PlanSummary {
+            combinations: results.len(),
+            distinct_plans: fingerprints.len(),
+            timing: TimingSummary::new(results),
+            subgraphs: SubgraphCoverage::new(results),
+            cheapest,
+            most_expensive,
+            depth_histogram,
+        }
+    }
+}
+
+impl TimingSummary {
+    fn new(results: &[QueryPlanResult]) -> Option<Self> {
+        let slowest = results
+            .iter()
+            .max_by(|a, b| a.planning_duration_ms.total_cmp(&b.planning_duration_ms))?;
+        let mut durations: Vec<f64> = results
+            .iter()
+            .map(|result| result.planning_duration_ms)
+            .collect();
+        durations.sort_by(f64::total_cmp);
+        let total_ms: f64 = durations.iter().sum();
+        Some(TimingSummary {
+            total_ms,
+            mean_ms: total_ms / durations.len() as f64,
+            p50_ms: percentile(&durations, 50),
+            p95_ms: percentile(&durations, 95),
+            max_ms: slowest.planning_duration_ms,
+            slowest_combination: slowest.query_plan_config.combination_name.clone(),
+        })
+    }
+}
+
+impl SubgraphCoverage {
+    fn new(results: &[QueryPlanResult]) -> Self {
+        let plan_subgraphs: Vec<_> = results.iter().map(|result| result.subgraphs()).collect();
+        let all: BTreeSet<String> = plan_subgraphs.iter().flatten().cloned().collect();
+        let override_dependent = all
+            .iter()
+            .filter(|subgraph| !plan_subgraphs.iter().all(|set| set.contains(*subgraph)))
+            .cloned()
+            .collect();
+        SubgraphCoverage {
+            all,
+            override_dependent,
+        }
+    }
+}
+
+/// The nearest-rank percentile of non-empty, sorted values.
+pub(crate) fn percentile(sorted: &[f64], percent: usize) -> f64 {
+    let rank = (sorted.len() * percent).div_ceil(100);
+    sorted[rank.saturating_sub(1)]
+}
+
+impl fmt::Display for PlanSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Evaluated {} combinations, {} distinct query plans",
+            self.combinations, self.distinct_plans
+        )?;
+        if let Some(timing) = &self.timing {
+            write!(f, "\n{timing}")?;
+        }
+        write!(f, "\n{}", self.subgraphs)?;
+        if let (Some(cheapest), Some(most_expensive)) = (&self.cheapest, &self.most_expensive) {
+            write!(
+                f,
+                "\nCheapest: {} (cost {})\nMost expensive: {} (cost {})",
+                cheapest.combination_name,
+                cheapest.cost,
+                most_expensive.combination_name,
+                most_expensive.cost
+            )?;
+        }
+        if !self.depth_histogram.is_empty() {
+            let buckets: Vec<_> = self
+                .depth_histogram
+                .iter()
+                .map(|(depth, count)| format!("depth {depth}: {count} combinations"))
+                .collect();
+            write!(f, "\nPlan depths: {}", buckets.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TimingSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Planning time: total {:.1}ms, mean {:.1}ms, p50 {:.1}ms, p95 {:.1}ms, max {:.1}ms (slowest: {})",
+            self.total_ms,
+            self.mean_ms,
+            self.p50_ms,
+            self.p95_ms,
+            self.max_ms,
+            self.slowest_combination
+        )
+    }
+}
+
+impl fmt::Display for SubgraphCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join =
+            |subgraphs: &BTreeSet<String>| subgraphs.iter().cloned().collect::<Vec<_>>().join(", ");
+        write!(f, "Subgraphs: {}", join(&self.all))?;
+        if !self.override_dependent.is_empty() {
+            write!(
+                f,
+                " (depending on override conditions: {})",
+                join(&self.override_dependent)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The statistics and the cost of a query plan
+#[derive(serde::Serialize)]
+pub(crate) struct PlanStats {
+    #[serde(flatten)]
+    pub(crate) stats: QueryPlanStats,
+    pub(crate) cost: f64,
+}
+
+impl PlanStats {
+    pub(crate) fn new(result: &QueryPlanResult, weights: &CostWeights) -> Self {
+        let stats = result.stats();
+        PlanStats {
+            cost: plan_cost(&stats, weights),
+            stats,
+        }
+    }
+}
+
+impl fmt::Display for PlanStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let QueryPlanStats {
+            fetch_count,
+            entity_fetch_count,
+            critical_path_length,
+            max_depth,
+            requires_count,
+            max_fan_out,
+            possible_n_plus_one: _,
+            n_plus_one_reason,
+        } = &self.stats;
+        write!(
+            f,
+            "Stats: {fetch_count} fetches ({entity_fetch_count} entity fetches), critical path {critical_path_length}, max depth {max_depth}, fan-out {max_fan_out}, {requires_count} required selections, cost {}",
+            self.cost
+        )?;
+        if let Some(reason) = n_plus_one_reason {
+            write!(f, "\nPossible N+1 pattern: {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The subgraphs that entered or left the query plan between two query plan results
+pub(crate) struct SubgraphChanges {
+    pub(crate) added: BTreeSet<String>,
+    pub(crate) removed: BTreeSet<String>,
+}
+
+impl SubgraphChanges {
+    pub(crate) fn new(from: &QueryPlanResult, to: &QueryPlanResult) -> Self {
+        let from = from.subgraphs();
+        let to = to.subgraphs();
+        SubgraphChanges {
+            added: to.difference(&from).cloned().collect(),
+            removed: from.difference(&to).cloned().collect(),
+        }
+    }
+}
+
+impl fmt::Display for SubgraphChanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Subgraphs added: {:?}, removed: {:?}",
+            self.added, self.removed
+        )
+    }
+}
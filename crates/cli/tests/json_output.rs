@@ -0,0 +1,58 @@
+use std::process::Command;
+use std::process::Output;
+
+const SUPERGRAPH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../example/supergraph.graphql"
+);
+const QUERY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../example/op.graphql");
+
+fn run_plan(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_qp-analyzer-cli"))
+        .arg("plan")
+        .args([SUPERGRAPH, QUERY, "--json"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Parses the whole stdout as a single JSON document (trailing documents are rejected).
+fn parse_stdout(output: &Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|e| {
+        panic!(
+            "stdout is not a single JSON document ({e}):\n{}",
+            String::from_utf8_lossy(&output.stdout)
+        )
+    })
+}
+
+#[test]
+fn failed_check_is_part_of_the_report() {
+    let output = run_plan(&["--max-sequential", "0"]);
+    assert!(!output.status.success());
+    let report = parse_stdout(&output);
+    assert!(report["results"].is_array());
+    assert!(
+        report["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("sequential fetches")
+    );
+}
+
+#[test]
+fn failed_check_of_all_operations_is_part_of_the_report() {
+    let output = run_plan(&["--all-operations", "--max-sequential", "0"]);
+    assert!(!output.status.success());
+    let report = parse_stdout(&output);
+    assert!(report["operations"].is_array());
+    assert!(report["error"]["message"].is_string());
+}
+
+#[test]
+fn successful_run_has_no_error() {
+    let output = run_plan(&[]);
+    assert!(output.status.success());
+    let report = parse_stdout(&output);
+    assert!(report.get("error").is_none());
+}
@@ -150,7 +150,7 @@ function handleBuildAllPlans(argv) {
     } else {
       plans.forEach((plan, i) => {
         console.log('-----------------------------------------------------------------------');
-        console.log(`Override Combination #${i}: ${JSON.stringify(plan.query_plan_config.override_conditions)}`);
+        console.log(`Override Combination #${i}: ${plan.query_plan_config.combination_name}`);
         console.log('-----------------------------------------------------------------------');
         console.log(plan.query_plan_display);
         console.log();
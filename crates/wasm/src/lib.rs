@@ -5,6 +5,7 @@ use serde::Deserialize;
 use std::num::NonZeroU32;
 use wasm_bindgen::prelude::*;
 
+use qp_analyzer::EnumerationMode;
 use qp_analyzer::QueryPlanResult;
 use qp_analyzer::compare_query_plans;
 use qp_analyzer::get_override_labels;
@@ -85,9 +86,14 @@ pub fn build_all_plans(
 ) -> Result<Vec<JsValue>, String> {
     let qp_args: QueryPlannerArgs =
         serde_wasm_bindgen::from_value(planner_args).map_err(|e| e.to_string())?;
-    let plans =
-        qp_analyzer::build_all_plans(schema_str, query_str, query_path, qp_args.into(), false)
-            .map_err(|e| e.to_string())?;
+    let plans = qp_analyzer::build_all_plans(
+        schema_str,
+        query_str,
+        query_path,
+        qp_args.into(),
+        EnumerationMode::All,
+    )
+    .map_err(|e| e.to_string())?;
 
     let js_values = plans
         .into_iter()